@@ -0,0 +1,313 @@
+use super::cube::{Cube, CubeError, Move};
+use super::kociemba::{self, KociembaSolver};
+use super::optimal::OptimalSolver;
+use super::thistlethwaite::{SolveError, ThistlethwaiteSolver};
+use std::any::Any;
+use std::collections::HashMap;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// A solver that a `BatchSolver` can share between its threads.
+pub trait Solver: Send + Sync + 'static {
+    type Error: Send + 'static;
+
+    /// Finds a sequence of face turns solving `cube`.
+    fn solve(&self, cube: &Cube) -> Result<Vec<Move>, Self::Error>;
+}
+
+impl Solver for ThistlethwaiteSolver {
+    type Error = SolveError;
+
+    fn solve(&self, cube: &Cube) -> Result<Vec<Move>, SolveError> {
+        ThistlethwaiteSolver::solve(self, cube)
+    }
+}
+
+impl Solver for KociembaSolver {
+    type Error = kociemba::SolveError;
+
+    fn solve(&self, cube: &Cube) -> Result<Vec<Move>, kociemba::SolveError> {
+        KociembaSolver::solve(self, cube)
+    }
+}
+
+impl Solver for OptimalSolver {
+    type Error = CubeError;
+
+    fn solve(&self, cube: &Cube) -> Result<Vec<Move>, CubeError> {
+        Ok(OptimalSolver::solve(self, cube)?.moves)
+    }
+}
+
+/// Measurements of a single solve in a batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SolveMetrics {
+    /// Time spent solving the cube.
+    pub duration: Duration,
+    /// Number of the thread that solved the cube, below the number of threads of the batch.
+    pub worker: usize,
+}
+
+/// The result of solving one cube of a batch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchSolve<E> {
+    /// Position of the cube in the input.
+    pub index: usize,
+    pub cube: Cube,
+    pub result: Result<Vec<Move>, E>,
+    pub metrics: SolveMetrics,
+}
+
+/**Solves many cubes on a pool of threads sharing a single solver, so the tables of the solver
+ * are loaded once for the whole batch rather than for every cube.
+ */
+pub struct BatchSolver<S> {
+    solver: Arc<S>,
+    threads: usize,
+    window: Option<usize>,
+}
+
+impl<S: Solver> BatchSolver<S> {
+    /// Batch solver using as many threads as the machine runs in parallel.
+    pub fn new(solver: S) -> Self {
+        Self::from_shared(Arc::new(solver))
+    }
+
+    /// Like `new`, for a solver that is also used elsewhere.
+    pub fn from_shared(solver: Arc<S>) -> Self {
+        let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+        Self { solver, threads, window: None }
+    }
+
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /**Solves at most `window` cubes ahead of the next result to be returned, which bounds the
+     * number of results held back while waiting for a slow solve. Defaults to four times the
+     * number of threads. The window is at least one cube.
+     */
+    pub fn with_window(mut self, window: usize) -> Self {
+        self.window = Some(window.max(1));
+        self
+    }
+
+    pub fn solver(&self) -> &S {
+        &self.solver
+    }
+
+    /**Solves every cube of `cubes`, returning the results in the order of the input as they
+     * become available. The cubes are taken from `cubes` as the threads get to them, so the
+     * input may be a stream too large to hold in memory. Results that are done ahead of their
+     * turn are held back, and the threads wait rather than get more than the window ahead of
+     * the next result, see `with_window`. A panic while solving is passed on by the returned
+     * iterator at once. Dropping the iterator stops the threads after their current solve.
+     */
+    pub fn solve<I>(&self, cubes: I) -> BatchResults<S::Error>
+    where
+        I: IntoIterator<Item = Cube>,
+        I::IntoIter: Send + 'static,
+    {
+        let input = Arc::new(Mutex::new(cubes.into_iter().enumerate()));
+        let window = Arc::new(Window {
+            size: self.window.unwrap_or(4 * self.threads),
+            returned: Mutex::new(0),
+            moved: Condvar::new(),
+            stopped: AtomicBool::new(false),
+        });
+        let (sender, receiver) = sync_channel(window.size);
+
+        let workers = (0..self.threads).map(|worker| {
+            let (solver, input, window, sender) = (self.solver.clone(), input.clone(), window.clone(), sender.clone());
+            std::thread::spawn(move || {
+                while !window.stopped.load(Ordering::Relaxed) {
+                    // A poisoned lock means the input panicked in another thread, which is
+                    // reported by `BatchResults`
+                    let next = match input.lock() {
+                        Ok(mut input) => input.next(),
+                        Err(_) => return,
+                    };
+                    let Some((index, cube)) = next else { return };
+                    if !window.wait_for(index) {
+                        return
+                    }
+
+                    let start = Instant::now();
+                    let result = std::panic::catch_unwind(AssertUnwindSafe(|| solver.solve(&cube)));
+                    let metrics = SolveMetrics { duration: start.elapsed(), worker };
+                    let message = result.map(|result| BatchSolve { index, cube, result, metrics });
+                    if sender.send(message).is_err() {
+                        return
+                    }
+                }
+            })
+        }).collect();
+
+        BatchResults { receiver, pending: HashMap::new(), next: 0, workers, window }
+    }
+}
+
+/// The range of indices the threads of a batch may solve, starting at the next result to return.
+struct Window {
+    size: usize,
+    /// Number of results returned so far.
+    returned: Mutex<usize>,
+    moved: Condvar,
+    stopped: AtomicBool,
+}
+
+impl Window {
+    /// Waits until `index` is in the window. False if the batch was stopped instead.
+    fn wait_for(&self, index: usize) -> bool {
+        let mut returned = self.returned.lock().unwrap();
+        while index >= *returned + self.size && !self.stopped.load(Ordering::Relaxed) {
+            returned = self.moved.wait(returned).unwrap();
+        }
+        return !self.stopped.load(Ordering::Relaxed)
+    }
+
+    fn advance(&self, returned: usize) {
+        *self.returned.lock().unwrap() = returned;
+        self.moved.notify_all();
+    }
+
+    fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        // Taking the lock makes sure no thread is between checking `stopped` and waiting
+        let _returned = self.returned.lock();
+        self.moved.notify_all();
+    }
+}
+
+/// A result of a batch, or the panic of the thread solving it.
+type Message<E> = Result<BatchSolve<E>, Box<dyn Any + Send>>;
+
+/// Iterator over the results of `BatchSolver::solve`, in the order of the input.
+pub struct BatchResults<E> {
+    receiver: Receiver<Message<E>>,
+    /// Results received ahead of their turn, by index.
+    pending: HashMap<usize, BatchSolve<E>>,
+    next: usize,
+    workers: Vec<JoinHandle<()>>,
+    window: Arc<Window>,
+}
+
+impl<E> Iterator for BatchResults<E> {
+    type Item = BatchSolve<E>;
+
+    fn next(&mut self) -> Option<BatchSolve<E>> {
+        while !self.pending.contains_key(&self.next) {
+            match self.receiver.recv() {
+                Ok(Ok(solve)) => { self.pending.insert(solve.index, solve); },
+                Ok(Err(panic)) => {
+                    self.window.stop();
+                    std::panic::resume_unwind(panic)
+                },
+                Err(_) => {
+                    // Every thread has stopped. Unless the input panicked, it is exhausted
+                    for worker in self.workers.drain(..) {
+                        if let Err(panic) = worker.join() {
+                            std::panic::resume_unwind(panic)
+                        }
+                    }
+                    return None
+                },
+            }
+        }
+        let solve = self.pending.remove(&self.next);
+        self.next += 1;
+        self.window.advance(self.next);
+        return solve
+    }
+}
+
+impl<E> Drop for BatchResults<E> {
+    fn drop(&mut self) {
+        self.window.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::scramble::random_cube;
+    use super::super::test_fixtures::{flipped_edge, shipped_solver};
+    use std::sync::atomic::AtomicUsize;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn solves_in_input_order() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut cubes: Vec<Cube> = (0..40).map(|_| random_cube(&mut rng)).collect();
+        cubes[13] = flipped_edge();
+
+        let batch = BatchSolver::new(shipped_solver()).with_threads(3);
+        let solves: Vec<_> = batch.solve(cubes.clone()).collect();
+        assert_eq!(solves.len(), cubes.len());
+        for (index, solve) in solves.iter().enumerate() {
+            assert_eq!(solve.index, index);
+            assert_eq!(solve.cube, cubes[index]);
+            assert_eq!(solve.result, batch.solver().solve(&cubes[index]));
+            assert!(solve.metrics.worker < 3);
+        }
+        assert_eq!(solves[13].result, Err(SolveError::InvalidCube(CubeError::FlippedEdge)));
+
+        // The input is taken lazily, and the threads stop once the results are dropped
+        let mut results = batch.solve(std::iter::repeat(Cube::new()));
+        assert!(results.next().unwrap().result.unwrap().is_empty());
+        drop(results);
+        assert!(batch.solve(Vec::new()).next().is_none());
+    }
+
+    /// Takes long for solved cubes, and panics for cubes with a flipped edge.
+    struct TestSolver {
+        solved: AtomicUsize,
+    }
+
+    impl Solver for TestSolver {
+        type Error = CubeError;
+
+        fn solve(&self, cube: &Cube) -> Result<Vec<Move>, CubeError> {
+            if cube.is_solved() {
+                std::thread::sleep(Duration::from_millis(300));
+            }
+            if cube.validate() == Err(CubeError::FlippedEdge) {
+                panic!("flipped edge");
+            }
+            self.solved.fetch_add(1, Ordering::Relaxed);
+            Ok(Vec::new())
+        }
+    }
+
+    #[test]
+    fn bounds_work_in_flight() {
+        let mut turned = Cube::new();
+        turned.turn(&Move::U);
+        let batch = BatchSolver::new(TestSolver { solved: AtomicUsize::new(0) }).with_threads(2).with_window(4);
+
+        // The other thread solves up to the window while waiting for the slow first solve
+        let mut results = batch.solve(std::iter::once(Cube::new()).chain(std::iter::repeat(turned)));
+        assert_eq!(results.next().unwrap().index, 0);
+        assert!(batch.solver().solved.load(Ordering::Relaxed) <= 5);
+        assert_eq!(results.next().unwrap().index, 1);
+    }
+
+    #[test]
+    fn passes_on_panics() {
+        let flipped = flipped_edge();
+        let mut turned = Cube::new();
+        turned.turn(&Move::U);
+
+        // The panic is passed on at once, even though the input never ends
+        let batch = BatchSolver::new(TestSolver { solved: AtomicUsize::new(0) }).with_threads(3);
+        let cubes = [turned, turned, flipped].into_iter().chain(std::iter::repeat(turned));
+        let panic = std::panic::catch_unwind(AssertUnwindSafe(|| batch.solve(cubes).count())).unwrap_err();
+        assert_eq!(panic.downcast_ref::<&str>(), Some(&"flipped edge"));
+    }
+}
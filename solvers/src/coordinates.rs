@@ -0,0 +1,507 @@
+use super::cube::{Cube, Edge, Move, Symmetry, CORNERS, EDGES, FACES};
+
+/**A coordinate describes part of the state of a cube, like the orientation of the edges, by a
+ * single number in `0..SIZE`. The coordinate of a turned cube only depends on the coordinate
+ * before the turn, so searches can turn coordinates using a `MoveTable` instead of turning
+ * whole cubes.
+ */
+pub trait Coordinate {
+    const SIZE: usize;
+    fn coordinate(cube: &Cube) -> usize;
+
+    /**A cube with coordinate `coordinate`. Pieces the coordinate does not depend on are placed
+     * arbitrarily, so the cube is not necessarily reachable by turning a solved cube.
+     */
+    fn cube(coordinate: usize) -> Cube;
+}
+
+/// The orientation of the first 11 edges as a base-2 number. The last follows from the rest.
+pub struct EdgeOrientation;
+impl Coordinate for EdgeOrientation {
+    const SIZE: usize = 2048;
+
+    fn coordinate(cube: &Cube) -> usize {
+        EDGES[..11].iter()
+            .enumerate()
+            .map(|(i, edge)| 2_usize.pow(i as u32) * cube.get_edge_orientation(edge) as usize)
+            .sum()
+    }
+
+    fn cube(coordinate: usize) -> Cube {
+        let orientations = digits(coordinate, 2, 12);
+        let edges = EDGES.map(|edge| (edge, orientations[edge as usize]));
+        return Cube::from_pieces(edges, CORNERS.map(|corner| (corner, 0)), FACES)
+    }
+}
+
+/// The orientation of the first 7 corners as a base-3 number. The last follows from the rest.
+pub struct CornerOrientation;
+impl Coordinate for CornerOrientation {
+    const SIZE: usize = 2187;
+
+    fn coordinate(cube: &Cube) -> usize {
+        CORNERS[..7].iter()
+            .enumerate()
+            .map(|(i, corner)| 3_usize.pow(i as u32) * cube.get_corner_orientation(corner) as usize)
+            .sum()
+    }
+
+    fn cube(coordinate: usize) -> Cube {
+        let orientations = digits(coordinate, 3, 8);
+        let corners = CORNERS.map(|corner| (corner, orientations[corner as usize]));
+        return Cube::from_pieces(EDGES.map(|edge| (edge, 0)), corners, FACES)
+    }
+}
+
+pub(crate) const E_SLICE_EDGES: [Edge; 4] = [Edge::RF, Edge::RB, Edge::LB, Edge::LF];
+pub(crate) const M_SLICE_EDGES: [Edge; 4] = [Edge::UF, Edge::DF, Edge::DB, Edge::UB];
+const UD_EDGES: [Edge; 8] = [Edge::UR, Edge::UB, Edge::UL, Edge::UF, Edge::DR, Edge::DB, Edge::DL, Edge::DF];
+
+/**The positions of the four E-slice edges, ignoring their order: (12 choose 4) = 495 values.
+ * The E-slice edges are in the E-slice for the value 69.
+ */
+pub struct ESliceCombination;
+impl Coordinate for ESliceCombination {
+    const SIZE: usize = 495;
+
+    fn coordinate(cube: &Cube) -> usize {
+        let positions = E_SLICE_EDGES.map(|edge| *cube.get_edge_position(&edge));
+        combination_rank(&positions, &EDGES)
+    }
+
+    fn cube(coordinate: usize) -> Cube {
+        let positions = combination_unrank::<4>(coordinate).map(|i| EDGES[i]);
+        let edges = place(&E_SLICE_EDGES, &positions, &EDGES);
+        return Cube::from_pieces(edges.map(|edge| (edge, 0)), CORNERS.map(|corner| (corner, 0)), FACES)
+    }
+}
+
+/// The permutation of the corners: 8! = 40320 values.
+pub struct CornerPermutation;
+impl Coordinate for CornerPermutation {
+    const SIZE: usize = 40320;
+
+    fn coordinate(cube: &Cube) -> usize {
+        let pieces = CORNERS.map(|pos| *cube.get_corner_type(&pos));
+        permutation_rank(&pieces, &CORNERS)
+    }
+
+    fn cube(coordinate: usize) -> Cube {
+        let permutation = permutation_unrank(coordinate, 8);
+        let corners = CORNERS.map(|pos| (CORNERS[permutation[pos as usize]], 0));
+        return Cube::from_pieces(EDGES.map(|edge| (edge, 0)), corners, FACES)
+    }
+}
+
+/**The permutation of the eight U and D layer edges: 8! = 40320 values. Only defined when
+ * these edges are all in the U and D layers.
+ */
+pub struct UDEdgePermutation;
+impl Coordinate for UDEdgePermutation {
+    const SIZE: usize = 40320;
+
+    fn coordinate(cube: &Cube) -> usize {
+        let pieces = UD_EDGES.map(|pos| *cube.get_edge_type(&pos));
+        permutation_rank(&pieces, &UD_EDGES)
+    }
+
+    fn cube(coordinate: usize) -> Cube {
+        let permutation = permutation_unrank(coordinate, 8);
+        let pieces: Vec<Edge> = permutation.iter().map(|&i| UD_EDGES[i]).collect();
+        let edges = EDGES.map(|pos| match UD_EDGES.iter().position(|&edge| edge == pos) {
+            Some(i) => pieces[i],
+            None => pos,
+        });
+        return Cube::from_pieces(edges.map(|edge| (edge, 0)), CORNERS.map(|corner| (corner, 0)), FACES)
+    }
+}
+
+/// The permutation of the four E-slice edges: 4! = 24 values. Only defined when they are in the E-slice.
+pub struct ESlicePermutation;
+impl Coordinate for ESlicePermutation {
+    const SIZE: usize = 24;
+
+    fn coordinate(cube: &Cube) -> usize {
+        slice_permutation(cube, &E_SLICE_EDGES)
+    }
+
+    fn cube(coordinate: usize) -> Cube {
+        slice_permutation_cube(coordinate, &E_SLICE_EDGES)
+    }
+}
+
+/// The permutation of the four M-slice edges: 4! = 24 values. Only defined when they are in the M-slice.
+pub struct MSlicePermutation;
+impl Coordinate for MSlicePermutation {
+    const SIZE: usize = 24;
+
+    fn coordinate(cube: &Cube) -> usize {
+        slice_permutation(cube, &M_SLICE_EDGES)
+    }
+
+    fn cube(coordinate: usize) -> Cube {
+        slice_permutation_cube(coordinate, &M_SLICE_EDGES)
+    }
+}
+
+fn slice_permutation(cube: &Cube, slice: &[Edge; 4]) -> usize {
+    let positions = slice.map(|edge| *cube.get_edge_position(&edge));
+    permutation_rank(&positions, slice)
+}
+
+fn slice_permutation_cube(coordinate: usize, slice: &[Edge; 4]) -> Cube {
+    let positions: Vec<Edge> = permutation_unrank(coordinate, 4).iter().map(|&i| slice[i]).collect();
+    let edges = place(slice, &positions, &EDGES);
+    return Cube::from_pieces(edges.map(|edge| (edge, 0)), CORNERS.map(|corner| (corner, 0)), FACES)
+}
+
+/**The coordinate after each move of a move pool, so turning a coordinate is a single lookup.
+ * The table is built by turning `C::cube` for every coordinate.
+ */
+pub struct MoveTable {
+    data: Box<[u16]>,
+    size: usize,
+    moves: usize,
+}
+
+impl MoveTable {
+    pub fn new<C: Coordinate>(moves: &[Move]) -> Self {
+        let mut data = Vec::with_capacity(C::SIZE * moves.len());
+        for coordinate in 0..C::SIZE {
+            let cube = C::cube(coordinate);
+            for turn in moves {
+                let mut next = cube;
+                next.turn(turn);
+                data.push(C::coordinate(&next) as u16);
+            }
+        }
+        return Self { data: data.into(), size: C::SIZE, moves: moves.len() }
+    }
+
+    /// Number of values of the coordinate.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The coordinate after applying the move with index `turn` in the move pool.
+    pub fn turn(&self, coordinate: usize, turn: usize) -> usize {
+        self.data[coordinate * self.moves + turn] as usize
+    }
+}
+
+/**Move tables for an index made of independent coordinates as a mixed-radix number, where
+ * each coordinate is `(index / step) % size` for its own step. Turning the index turns each
+ * coordinate by its own table.
+ */
+pub struct CombinedMoveTable {
+    tables: Vec<(usize, MoveTable)>,
+}
+
+impl CombinedMoveTable {
+    /// The tables of each coordinate along with their steps.
+    pub fn new(tables: Vec<(usize, MoveTable)>) -> Self {
+        Self { tables }
+    }
+
+    pub fn turn(&self, index: usize, turn: usize) -> usize {
+        self.tables.iter()
+            .map(|(step, table)| table.turn(index / step % table.size(), turn) * step)
+            .sum()
+    }
+}
+
+/**The classes of values of a coordinate that are conjugates of each other by a group of
+ * symmetries. Each class is represented by its smallest value. A table over classes instead
+ * of values is up to `symmetries.len()` times smaller, and holds the same distances as long
+ * as the move pool is mapped to itself by the symmetries. Conjugating a cube by a symmetry
+ * must change the coordinate in a way that only depends on the coordinate.
+ */
+pub struct SymmetryReduction {
+    /// The class of each value times the number of symmetries, plus the symmetry taking it to the representative.
+    classes: Box<[u32]>,
+    representatives: Box<[u32]>,
+    symmetries: Vec<Symmetry>,
+}
+
+impl SymmetryReduction {
+    /// The classes of `C` under `symmetries`, which must be closed under composition and inverses.
+    pub fn new<C: Coordinate>(symmetries: &[Symmetry]) -> Self {
+        let n = symmetries.len();
+        let inverses: Vec<usize> = symmetries.iter()
+            .map(|symmetry| symmetries.iter().position(|other| *other == symmetry.inverse()).unwrap())
+            .collect();
+
+        let mut classes = vec![u32::MAX; C::SIZE];
+        let mut representatives = Vec::new();
+        for coordinate in 0..C::SIZE {
+            if classes[coordinate] != u32::MAX {
+                continue
+            }
+            let class = representatives.len();
+            representatives.push(coordinate as u32);
+            let cube = C::cube(coordinate);
+            for (i, symmetry) in symmetries.iter().enumerate() {
+                // The inverse symmetry takes the conjugate back to the representative
+                let conjugate = C::coordinate(&cube.conjugate(symmetry));
+                if classes[conjugate] == u32::MAX {
+                    classes[conjugate] = (class * n + inverses[i]) as u32;
+                }
+            }
+        }
+        return Self { classes: classes.into(), representatives: representatives.into(), symmetries: symmetries.to_vec() }
+    }
+
+    /// Number of classes.
+    pub fn classes(&self) -> usize {
+        self.representatives.len()
+    }
+
+    pub fn symmetries(&self) -> &[Symmetry] {
+        &self.symmetries
+    }
+
+    /**The class of `coordinate`, along with the index of a symmetry conjugating cubes with
+     * this coordinate into cubes with the representative of the class.
+     */
+    pub fn reduce(&self, coordinate: usize) -> (usize, usize) {
+        let packed = self.classes[coordinate] as usize;
+        (packed / self.symmetries.len(), packed % self.symmetries.len())
+    }
+
+    /// The smallest value of the coordinate in `class`.
+    pub fn representative(&self, class: usize) -> usize {
+        self.representatives[class] as usize
+    }
+}
+
+/**The coordinate after conjugating by each of a list of symmetries, for coordinates where the
+ * result only depends on the coordinate. Used to carry the remaining coordinates of an index
+ * along when another coordinate is reduced to its representative.
+ */
+pub struct ConjugationTable {
+    data: Box<[u16]>,
+    symmetries: usize,
+}
+
+impl ConjugationTable {
+    pub fn new<C: Coordinate>(symmetries: &[Symmetry]) -> Self {
+        let mut data = Vec::with_capacity(C::SIZE * symmetries.len());
+        for coordinate in 0..C::SIZE {
+            let cube = C::cube(coordinate);
+            for symmetry in symmetries {
+                data.push(C::coordinate(&cube.conjugate(symmetry)) as u16);
+            }
+        }
+        return Self { data: data.into(), symmetries: symmetries.len() }
+    }
+
+    /// The coordinate after conjugating by the symmetry with index `symmetry`.
+    pub fn conjugate(&self, coordinate: usize, symmetry: usize) -> usize {
+        self.data[coordinate * self.symmetries + symmetry] as usize
+    }
+}
+
+/**Computes a unique index for `combination` between all similar sized combinations of
+ * `ordering`. (a combination is an unordered subset). In particular, the computed index
+ * is the co-lexicographic rank of the combination, where the lexicographic ordering is
+ * defined by `ordering`.
+ *
+ * Implementation from: https://computationalcombinatorics.wordpress.com/2012/09/10/ranking-and-unranking-of-combinations-and-permutations/
+ * First the combination of items is transformed to an integer combination. Then the rank
+ * of this combination is computed by the formula: rank(S) = Σ_{i=0}^{k-1} (S_i choose i+1),
+ * where S is the list of integers in increasing order. Justification of the formula is not
+ * very well described at the source. Will have to investigate this at some point.
+ */
+pub(crate) fn combination_rank<T: PartialEq>(combination: &[T], ordering: &[T]) -> usize {
+    let mut combination: Vec<usize> = combination.iter()
+        .map(|x| ordering.iter().position(|y| x == y).unwrap())
+        .collect();
+    combination.sort();
+    combination.iter()
+        .enumerate()
+        .map(|(i, &elem)| binom(elem, i + 1))
+        .sum()
+}
+
+/**Inverse of `combination_rank` with the ordering `0..n` for any large enough `n`: the
+ * increasing `K` integers with co-lexicographic rank `rank`.
+ */
+pub(crate) fn combination_unrank<const K: usize>(mut rank: usize) -> [usize; K] {
+    let mut combination = [0; K];
+    for i in (0..K).rev() {
+        // The largest element is the largest c with (c choose i+1) <= rank
+        let mut c = i;
+        while binom(c + 1, i + 1) <= rank {
+            c += 1;
+        }
+        combination[i] = c;
+        rank -= binom(c, i + 1);
+    }
+    return combination
+}
+
+/// Computes the binomial coefficient (n choose k).
+pub(crate) fn binom(n: usize, k: usize) -> usize {
+    if k > n {
+        0
+    } else {
+        (0..k).fold(1, |res, i| (res * (n - i)) / (i + 1))
+    }
+}
+
+/// Lexicographic rank of a permutation
+pub(crate) fn permutation_rank<T: PartialEq>(permutation: &[T], initial: &[T]) -> usize {
+    let pi: Vec<usize> = permutation.iter()
+        .map(|x| initial.iter().position(|y| x == y).unwrap())
+        .collect();
+
+    let n = pi.len();
+    let mut digits: Vec<usize> = (0..n).collect();
+    let mut factorial: usize = (2..n).product();
+    let mut index = 0;
+    for (i, pi_i) in pi.iter().enumerate().take(n - 1) {
+        let q = digits.iter()
+            .position(|x| x == pi_i)
+            .unwrap();
+        index += factorial * q;
+        digits.remove(q);
+        factorial /= n - 1 - i;
+    }
+    return index;
+}
+
+/// Inverse of `permutation_rank` with the initial permutation `0..n`.
+pub(crate) fn permutation_unrank(mut rank: usize, n: usize) -> Vec<usize> {
+    let mut digits: Vec<usize> = (0..n).collect();
+    let mut factorial: usize = (1..n).product();
+    let mut permutation = Vec::new();
+    for i in 0..n {
+        let q = rank / factorial;
+        rank %= factorial;
+        permutation.push(digits.remove(q));
+        if i + 1 < n {
+            factorial /= n - 1 - i;
+        }
+    }
+    return permutation
+}
+
+pub(crate) fn permutation_parity<T: PartialEq>(permutation: &[T], initial: &[T]) -> bool {
+    let permutation: Vec<usize> = permutation.iter()
+        .map(|x| initial.iter().position(|y| x == y).unwrap())
+        .collect();
+
+    let mut parity = false;
+    for i in 0..permutation.len() {
+        for j in i+1..permutation.len() {
+            parity ^= permutation[i] > permutation[j];
+        }
+    }
+    return parity
+}
+
+/// The digits of `number` in base `base`, least significant first, with a last digit making the sum 0 mod `base`.
+pub(crate) fn digits(mut number: usize, base: usize, count: usize) -> Vec<u8> {
+    let mut digits = Vec::new();
+    for _ in 0..count - 1 {
+        digits.push((number % base) as u8);
+        number /= base;
+    }
+    let sum: usize = digits.iter().map(|&digit| digit as usize).sum();
+    digits.push(((base - sum % base) % base) as u8);
+    return digits
+}
+
+/**The piece at each position of `all` when `pieces[i]` is placed at `positions[i]`, and the
+ * remaining pieces fill the remaining positions in order.
+ */
+pub(crate) fn place<T: Copy + PartialEq, const N: usize>(pieces: &[T], positions: &[T], all: &[T; N]) -> [T; N] {
+    let mut others = all.iter().filter(|piece| !pieces.contains(piece));
+    return all.map(|pos| match positions.iter().position(|&p| p == pos) {
+        Some(i) => pieces[i],
+        None => *others.next().unwrap(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::cube::MOVES;
+
+    fn roundtrip<C: Coordinate>() {
+        for coordinate in 0..C::SIZE {
+            assert_eq!(C::coordinate(&C::cube(coordinate)), coordinate);
+        }
+    }
+
+    #[test]
+    fn cubes_have_their_coordinate() {
+        roundtrip::<EdgeOrientation>();
+        roundtrip::<CornerOrientation>();
+        roundtrip::<ESliceCombination>();
+        roundtrip::<CornerPermutation>();
+        roundtrip::<UDEdgePermutation>();
+        roundtrip::<ESlicePermutation>();
+        roundtrip::<MSlicePermutation>();
+
+        let solved = Cube::new();
+        assert_eq!(EdgeOrientation::coordinate(&solved), 0);
+        assert_eq!(ESliceCombination::coordinate(&solved), 69);
+        assert_eq!(CornerPermutation::coordinate(&solved), 0);
+    }
+
+    #[test]
+    fn move_tables_agree_with_cube() {
+        let orientation = MoveTable::new::<CornerOrientation>(&MOVES);
+        let permutation = MoveTable::new::<CornerPermutation>(&MOVES);
+        let combined = CombinedMoveTable::new(vec![
+            (40320, MoveTable::new::<CornerOrientation>(&MOVES)),
+            (1, MoveTable::new::<CornerPermutation>(&MOVES)),
+        ]);
+
+        let mut cube = Cube::new();
+        for turn in [Move::R, Move::F, Move::Up, Move::B2, Move::L, Move::D] {
+            let i = turn as usize;
+            let (twist, corners) = (CornerOrientation::coordinate(&cube), CornerPermutation::coordinate(&cube));
+            cube.turn(&turn);
+            assert_eq!(orientation.turn(twist, i), CornerOrientation::coordinate(&cube));
+            assert_eq!(permutation.turn(corners, i), CornerPermutation::coordinate(&cube));
+            assert_eq!(
+                combined.turn(twist * 40320 + corners, i),
+                CornerOrientation::coordinate(&cube) * 40320 + CornerPermutation::coordinate(&cube),
+            );
+        }
+    }
+
+    #[test]
+    fn symmetry_reduction() {
+        let symmetries = Symmetry::preserving_ud_axis();
+        let reduction = SymmetryReduction::new::<CornerPermutation>(&symmetries);
+        let orientations = ConjugationTable::new::<CornerOrientation>(&symmetries);
+        assert_eq!(reduction.classes(), 2768);
+        assert_eq!(reduction.reduce(0), (0, 0));
+
+        let mut cube = Cube::new();
+        for turn in [Move::R, Move::F, Move::Up, Move::B2, Move::L, Move::D] {
+            cube.turn(&turn);
+            let (class, symmetry) = reduction.reduce(CornerPermutation::coordinate(&cube));
+            let conjugate = cube.conjugate(&reduction.symmetries()[symmetry]);
+            assert_eq!(CornerPermutation::coordinate(&conjugate), reduction.representative(class));
+            assert_eq!(orientations.conjugate(CornerOrientation::coordinate(&cube), symmetry), CornerOrientation::coordinate(&conjugate));
+        }
+    }
+
+    #[test]
+    fn ranks() {
+        assert_eq!(binom(12, 4), 495);
+        assert_eq!(permutation_rank(&[0, 1, 2, 3], &[0, 1, 2, 3]), 0);
+        assert_eq!(permutation_rank(&[3, 2, 1, 0], &[0, 1, 2, 3]), 23);
+        assert_eq!(combination_rank(&[Edge::UB, Edge::UR], &EDGES), 0);
+        assert_eq!(combination_rank(&[Edge::UR, Edge::UL], &EDGES), 1);
+        assert!(permutation_parity(&[1, 0, 2], &[0, 1, 2]));
+        assert!(!permutation_parity(&[1, 2, 0], &[0, 1, 2]));
+        assert_eq!(permutation_unrank(23, 4), [3, 2, 1, 0]);
+        assert_eq!(combination_unrank::<4>(494), [8, 9, 10, 11]);
+        assert_eq!(combination_unrank::<2>(1), [0, 2]);
+    }
+}
@@ -1,4 +1,6 @@
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use super::coordinates::permutation_parity;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Edge {
     UR, UB, UL, UF, // top layer
     RF, RB, LB, LF, // middle layer
@@ -11,7 +13,7 @@ pub const EDGES: [Edge; 12] = [
     Edge::DR, Edge::DB, Edge::DL, Edge::DF,
 ];
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Corner {
     URF, URB, ULB, ULF, // top layer
     DRB, DRF, DLF, DLB, // bottom layer
@@ -22,7 +24,124 @@ pub const CORNERS: [Corner; 8] = [
     Corner::DRB, Corner::DRF, Corner::DLF, Corner::DLB,
 ];
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Face {
+    U, L, D, R, F, B,
+}
+
+pub const FACES: [Face; 6] = [Face::U, Face::L, Face::D, Face::R, Face::F, Face::B];
+
+impl Face {
+    pub fn opposite(&self) -> Face {
+        match self {
+            Face::U => Face::D,
+            Face::L => Face::R,
+            Face::D => Face::U,
+            Face::R => Face::L,
+            Face::F => Face::B,
+            Face::B => Face::F,
+        }
+    }
+
+    /// The face that `face` is moved to by a clockwise quarter turn around the axis through `self`.
+    fn quarter_turn(&self, face: Face) -> Face {
+        // Each cycle lists the faces around `self` in clockwise order
+        let cycle = match self {
+            Face::U => [Face::F, Face::L, Face::B, Face::R],
+            Face::L => [Face::F, Face::D, Face::B, Face::U],
+            Face::D => [Face::F, Face::R, Face::B, Face::L],
+            Face::R => [Face::F, Face::U, Face::B, Face::D],
+            Face::F => [Face::U, Face::R, Face::D, Face::L],
+            Face::B => [Face::U, Face::L, Face::D, Face::R],
+        };
+        match cycle.iter().position(|&f| f == face) {
+            Some(i) => cycle[(i + 1) % 4],
+            None => face,
+        }
+    }
+}
+
+/**The facelets of each edge position. The first facelet is the reference used for the 
+ * orientation: an edge has orientation 0 if its U/D sticker (F/B sticker for edges 
+ * belonging in the middle layer) is on the reference facelet.
+ */
+pub(crate) const EDGE_FACELETS: [[Face; 2]; 12] = [
+    [Face::U, Face::R], [Face::U, Face::B], [Face::U, Face::L], [Face::U, Face::F],
+    [Face::F, Face::R], [Face::B, Face::R], [Face::B, Face::L], [Face::F, Face::L],
+    [Face::D, Face::R], [Face::D, Face::B], [Face::D, Face::L], [Face::D, Face::F],
+];
+
+/**The facelets of each corner position in clockwise order, starting from the U/D facelet. 
+ * A corner has orientation `n` if its U/D sticker is `n` steps counter-clockwise from the
+ * U/D facelet.
+ */
+pub(crate) const CORNER_FACELETS: [[Face; 3]; 8] = [
+    [Face::U, Face::R, Face::F], [Face::U, Face::B, Face::R],
+    [Face::U, Face::L, Face::B], [Face::U, Face::F, Face::L],
+    [Face::D, Face::R, Face::B], [Face::D, Face::F, Face::R],
+    [Face::D, Face::L, Face::F], [Face::D, Face::B, Face::L],
+];
+
+/// The slices between two opposite faces. Each slice turns in the same direction as the named face.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Slice {
+    /// Between L and R, turning like L.
+    M,
+    /// Between U and D, turning like D.
+    E,
+    /// Between F and B, turning like F.
+    S,
+}
+
+impl Slice {
+    pub fn face(&self) -> Face {
+        match self {
+            Slice::M => Face::L,
+            Slice::E => Face::D,
+            Slice::S => Face::F,
+        }
+    }
+}
+
+/// Axes of whole cube rotations. Each rotation turns in the same direction as the named face.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Axis {
+    /// Turning like R.
+    X,
+    /// Turning like U.
+    Y,
+    /// Turning like F.
+    Z,
+}
+
+impl Axis {
+    pub fn face(&self) -> Face {
+        match self {
+            Axis::X => Face::R,
+            Axis::Y => Face::U,
+            Axis::Z => Face::F,
+        }
+    }
+}
+
+/**Moves beyond the face turns of `Move`, as used in reconstructions of real solves. The 
+ * amount is the number of quarter turns in the direction of the move, that is 1, 2 or 3.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExtendedMove {
+    Face(Move),
+    Slice(Slice, u8),
+    Wide(Face, u8),
+    Rotation(Axis, u8),
+}
+
+impl From<Move> for ExtendedMove {
+    fn from(turn: Move) -> Self {
+        ExtendedMove::Face(turn)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Move {
     U, Up, U2,
     L, Lp, L2,
@@ -32,7 +151,129 @@ pub enum Move {
     B, Bp, B2,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub const MOVES: [Move; 18] = [
+    Move::U, Move::Up, Move::U2,
+    Move::L, Move::Lp, Move::L2,
+    Move::D, Move::Dp, Move::D2,
+    Move::R, Move::Rp, Move::R2,
+    Move::F, Move::Fp, Move::F2,
+    Move::B, Move::Bp, Move::B2,
+];
+
+impl Move {
+    /// The face turned by the move.
+    pub fn face(&self) -> Face {
+        FACES[*self as usize / 3]
+    }
+
+    /// Number of clockwise quarter turns of the face, that is 1, 2 or 3.
+    pub fn amount(&self) -> u8 {
+        match *self as usize % 3 {
+            0 => 1,
+            1 => 3,
+            _ => 2,
+        }
+    }
+
+    /// The move turning `face` clockwise by `amount` quarter turns. `None` if `amount` is 0 mod 4.
+    pub fn from_face_and_amount(face: Face, amount: u8) -> Option<Move> {
+        let offset = match amount % 4 {
+            0 => return None,
+            1 => 0,
+            2 => 2,
+            _ => 1,
+        };
+        Some(MOVES[face as usize * 3 + offset])
+    }
+
+    /// The move undoing this move.
+    pub fn inverse(&self) -> Move {
+        Move::from_face_and_amount(self.face(), 4 - self.amount()).unwrap()
+    }
+
+    /**Whether the move may follow `last` in a search. Turning the same face twice in a row is
+     * never needed, and turns of opposite faces commute, so those are only tried in one order.
+     */
+    pub(crate) fn may_follow(&self, last: Option<&Move>) -> bool {
+        let Some(last) = last else {
+            return true
+        };
+        let (last, face) = (last.face(), self.face());
+        return last != face && !(last == face.opposite() && (face as usize) < (last as usize))
+    }
+}
+
+/**A symmetry of the cube: one of the 24 rotations, possibly combined with a reflection,
+ * given by the face each face is moved to. Conjugating a cube by a symmetry relabels its
+ * stickers, so the conjugate needs as many moves to solve as the cube itself.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Symmetry {
+    faces: [Face; 6],
+}
+
+impl Symmetry {
+    /// All 48 symmetries, starting with the identity.
+    pub fn all() -> Vec<Symmetry> {
+        let mut symmetries = Vec::new();
+        for up in FACES {
+            for front in [Face::F, Face::B, Face::U, Face::D, Face::L, Face::R] {
+                if front == up || front == up.opposite() {
+                    continue
+                }
+                // The right face making the corner URF keep its handedness, and its opposite
+                let right = FACES.into_iter()
+                    .find(|&right| centers_form_rotation(&Self::from_axes(up, right, front).faces))
+                    .unwrap();
+                symmetries.push(Self::from_axes(up, right, front));
+                symmetries.push(Self::from_axes(up, right.opposite(), front));
+            }
+        }
+        return symmetries
+    }
+
+    /**The 16 symmetries mapping the U and D faces to themselves or each other. These keep
+     * the U/D facelets on U/D facelets, so they preserve the orientation of corners.
+     */
+    pub fn preserving_ud_axis() -> Vec<Symmetry> {
+        Self::all().into_iter().filter(|symmetry| matches!(symmetry.face(Face::U), Face::U | Face::D)).collect()
+    }
+
+    fn from_axes(up: Face, right: Face, front: Face) -> Symmetry {
+        let mut faces = [Face::U; 6];
+        for (face, image) in [(Face::U, up), (Face::R, right), (Face::F, front)] {
+            faces[face as usize] = image;
+            faces[face.opposite() as usize] = image.opposite();
+        }
+        Symmetry { faces }
+    }
+
+    /// The face that `face` is moved to.
+    pub fn face(&self, face: Face) -> Face {
+        self.faces[face as usize]
+    }
+
+    /// Whether the symmetry mirrors the cube, turning clockwise turns into counter-clockwise ones.
+    pub fn is_reflection(&self) -> bool {
+        !centers_form_rotation(&self.faces)
+    }
+
+    pub fn inverse(&self) -> Symmetry {
+        let mut faces = self.faces;
+        for face in FACES {
+            faces[self.face(face) as usize] = face;
+        }
+        Symmetry { faces }
+    }
+
+    /// The move doing to a conjugated cube what `turn` does to the cube.
+    pub fn turn(&self, turn: &Move) -> Move {
+        let amount = if self.is_reflection() { 4 - turn.amount() } else { turn.amount() };
+        Move::from_face_and_amount(self.face(turn.face()), amount).unwrap()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 struct Cubie<T> {
     piece_type: T,
     orientation: u8,
@@ -60,21 +301,130 @@ impl Cubie<Corner> {
     }
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+/// Reasons a `Cube` cannot be reached by turning a solved cube.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CubeError {
+    /// An edge appears at more than one position, so another edge is missing.
+    DuplicateEdge { duplicate: Edge, missing: Edge },
+    /// A corner appears at more than one position, so another corner is missing.
+    DuplicateCorner { duplicate: Corner, missing: Corner },
+    /// An edge orientation is not 0 or 1, or a corner orientation is not 0, 1 or 2.
+    InvalidOrientation,
+    /// The centers are not a rotation of the centers on a solved cube.
+    InvalidCenters,
+    /// The corner orientations add to `twist` (1 or 2) mod 3 instead of 0.
+    TwistedCorner { twist: u8 },
+    /// The edge orientations add to an odd number.
+    FlippedEdge,
+    /// The corner and edge permutations have different parity.
+    Parity,
+}
+
+impl std::fmt::Display for CubeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CubeError::DuplicateEdge { duplicate, missing } => {
+                write!(f, "edge {:?} appears more than once and edge {:?} is missing", duplicate, missing)
+            },
+            CubeError::DuplicateCorner { duplicate, missing } => {
+                write!(f, "corner {:?} appears more than once and corner {:?} is missing", duplicate, missing)
+            },
+            CubeError::InvalidOrientation => write!(f, "a piece has an orientation out of range"),
+            CubeError::InvalidCenters => write!(f, "the centers do not form a cube"),
+            CubeError::TwistedCorner { twist } => write!(f, "the corners are twisted by {} in total", twist),
+            CubeError::FlippedEdge => write!(f, "an odd number of edges are flipped"),
+            CubeError::Parity => write!(f, "the corner and edge permutations have different parity"),
+        }
+    }
+}
+
+impl std::error::Error for CubeError {}
+
+/// Selects the pieces turned by a move around the axis through the face, given their facelets.
+type LayerFilter = fn(Face, &[Face]) -> bool;
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub struct Cube {
     edges: [Cubie<Edge>; 12],
     corners: [Cubie<Corner>; 8],
+    centers: [Face; 6],
+}
+
+impl Default for Cube {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Cube {
     pub fn new() -> Self {
         Cube{
             edges: EDGES.map(|edge| Cubie{piece_type: edge, orientation: 0}),
-            corners: CORNERS.map(|corner| Cubie{piece_type: corner, orientation: 0})
+            corners: CORNERS.map(|corner| Cubie{piece_type: corner, orientation: 0}),
+            centers: FACES,
+        }
+    }
+
+    /**Creates a cube from the piece at each position along with its orientation, and the 
+     * center at each position. Positions are indexed by `Edge`, `Corner` and `Face` 
+     * respectively. No check is made that the state is reachable by turning a cube.
+     */
+    pub fn from_pieces(edges: [(Edge, u8); 12], corners: [(Corner, u8); 8], centers: [Face; 6]) -> Self {
+        Cube{
+            edges: edges.map(|(edge, orientation)| Cubie{piece_type: edge, orientation}),
+            corners: corners.map(|(corner, orientation)| Cubie{piece_type: corner, orientation}),
+            centers,
+        }
+    }
+
+    /**Checks that the state can be reached by turning a solved cube. That is, every piece 
+     * appears exactly once, the corner twists add to 0 mod 3, the edge flips add to 0 mod 2,
+     * and the corner and edge permutations have the same parity.
+     */
+    pub fn validate(&self) -> Result<(), CubeError> {
+        for (i, edge) in self.edges.iter().enumerate() {
+            if self.edges[i+1..].iter().any(|other| other.piece_type == edge.piece_type) {
+                let missing = EDGES.iter().find(|&&pos| self.edges.iter().all(|e| e.piece_type != pos)).unwrap();
+                return Err(CubeError::DuplicateEdge { duplicate: edge.piece_type, missing: *missing })
+            }
+        }
+        for (i, corner) in self.corners.iter().enumerate() {
+            if self.corners[i+1..].iter().any(|other| other.piece_type == corner.piece_type) {
+                let missing = CORNERS.iter().find(|&&pos| self.corners.iter().all(|c| c.piece_type != pos)).unwrap();
+                return Err(CubeError::DuplicateCorner { duplicate: corner.piece_type, missing: *missing })
+            }
         }
+        if self.edges.iter().any(|edge| edge.orientation > 1) || self.corners.iter().any(|corner| corner.orientation > 2) {
+            return Err(CubeError::InvalidOrientation)
+        }
+
+        if !centers_form_rotation(&self.centers) {
+            return Err(CubeError::InvalidCenters)
+        }
+
+        // Rotating the whole cube permutes the edges and corners with different parity, so the
+        // remaining invariants are checked with the centers in their home position
+        let cube = self.reoriented();
+        let twist = cube.corners.iter().map(|corner| corner.orientation).sum::<u8>() % 3;
+        if twist != 0 {
+            return Err(CubeError::TwistedCorner { twist })
+        }
+        if cube.edges.iter().map(|edge| edge.orientation).sum::<u8>() % 2 != 0 {
+            return Err(CubeError::FlippedEdge)
+        }
+        let edge_parity = permutation_parity(&cube.edges.map(|edge| edge.piece_type), &EDGES);
+        let corner_parity = permutation_parity(&cube.corners.map(|corner| corner.piece_type), &CORNERS);
+        if edge_parity != corner_parity {
+            return Err(CubeError::Parity)
+        }
+        return Ok(())
     }
 
+    /// Whether the cube is solved, regardless of how it is rotated as a whole.
     pub fn is_solved(&self) -> bool {
+        if self.centers != FACES {
+            return self.reoriented().is_solved()
+        }
         for (i, edge) in self.edges.iter().enumerate() {
             if edge.piece_type as usize != i || edge.orientation != 0 {
                 return false
@@ -88,6 +438,72 @@ impl Cube {
         return true
     }
 
+    /**The state reached by first applying the moves of `self` and then the moves of `other`
+     * to a solved cube. With this, cube states form a group with `Cube::new()` as identity.
+     */
+    pub fn compose(&self, other: &Cube) -> Cube {
+        // Position `pos` of the composition holds what `self` has at the position that `other`
+        // moves to `pos`, with the orientation changes of both added.
+        let edges = other.edges.map(|cubie| {
+            let mut edge = self.edges[cubie.piece_type as usize];
+            edge.orientation = (edge.orientation + cubie.orientation) % 2;
+            edge
+        });
+        let corners = other.corners.map(|cubie| {
+            let mut corner = self.corners[cubie.piece_type as usize];
+            corner.rotate(cubie.orientation);
+            corner
+        });
+        let centers = other.centers.map(|center| self.centers[center as usize]);
+        Cube{edges, corners, centers}
+    }
+
+    /// The state undoing `self`, that is `self.compose(&self.inverse()) == Cube::new()`.
+    pub fn inverse(&self) -> Cube {
+        let mut inverse = *self;
+        for (pos, cubie) in EDGES.iter().zip(self.edges.iter()) {
+            inverse.edges[cubie.piece_type as usize] = Cubie{piece_type: *pos, orientation: cubie.orientation};
+        }
+        for (pos, cubie) in CORNERS.iter().zip(self.corners.iter()) {
+            inverse.corners[cubie.piece_type as usize] = Cubie{piece_type: *pos, orientation: (3 - cubie.orientation) % 3};
+        }
+        for (pos, center) in FACES.iter().zip(self.centers.iter()) {
+            inverse.centers[*center as usize] = *pos;
+        }
+        inverse
+    }
+
+    /// Whether every piece is in the same position in both cubes, regardless of orientation.
+    pub fn same_permutation(&self, other: &Cube) -> bool {
+        self.edges.iter().zip(other.edges.iter()).all(|(a, b)| a.piece_type == b.piece_type)
+            && self.corners.iter().zip(other.corners.iter()).all(|(a, b)| a.piece_type == b.piece_type)
+            && self.centers == other.centers
+    }
+
+    /// Whether the cubes are equal after rotating each of them to have centers in their home position.
+    pub fn equivalent(&self, other: &Cube) -> bool {
+        self.reoriented() == other.reoriented()
+    }
+
+    /**The cube with every sticker moved and recoloured by `symmetry`. Turning the conjugate
+     * by `symmetry.turn(turn)` gives the conjugate of the turned cube.
+     */
+    pub fn conjugate(&self, symmetry: &Symmetry) -> Cube {
+        let mut cube = *self;
+        for (pos, cubie) in self.edges.iter().enumerate() {
+            let (pos, piece_type, orientation) = conjugate_cubie(&EDGE_FACELETS, symmetry, pos, cubie.piece_type as usize, cubie.orientation);
+            cube.edges[pos] = Cubie{piece_type: EDGES[piece_type], orientation};
+        }
+        for (pos, cubie) in self.corners.iter().enumerate() {
+            let (pos, piece_type, orientation) = conjugate_cubie(&CORNER_FACELETS, symmetry, pos, cubie.piece_type as usize, cubie.orientation);
+            cube.corners[pos] = Cubie{piece_type: CORNERS[piece_type], orientation};
+        }
+        for (pos, center) in FACES.iter().zip(self.centers.iter()) {
+            cube.centers[symmetry.face(*pos) as usize] = symmetry.face(*center);
+        }
+        cube
+    }
+
     pub fn get_edge_orientation(&self, pos: &Edge) -> u8 {
         self.edges[*pos as usize].orientation
     }
@@ -112,6 +528,47 @@ impl Cube {
         CORNERS.iter().find(|&&pos| self.corners[pos as usize].piece_type == *corner).unwrap()
     }
 
+    /// The center at position `pos`. Only whole cube rotations, slice and wide moves move centers.
+    pub fn get_center(&self, pos: &Face) -> &Face {
+        &self.centers[*pos as usize]
+    }
+
+    /**The same state rotated as a whole such that all centers are in their home position.
+     * This is the state that face turn solvers work on.
+     */
+    pub fn reoriented(&self) -> Cube {
+        let mut cube = *self;
+
+        // Bring the U center on top, then turn around the U axis until F is in front
+        let rotation = match FACES.iter().find(|&&pos| cube.centers[pos as usize] == Face::U).unwrap() {
+            Face::U => None,
+            Face::D => Some(ExtendedMove::Rotation(Axis::X, 2)),
+            Face::F => Some(ExtendedMove::Rotation(Axis::X, 1)),
+            Face::B => Some(ExtendedMove::Rotation(Axis::X, 3)),
+            Face::L => Some(ExtendedMove::Rotation(Axis::Z, 1)),
+            Face::R => Some(ExtendedMove::Rotation(Axis::Z, 3)),
+        };
+        if let Some(rotation) = rotation {
+            cube.turn_extended(&rotation);
+        }
+        while cube.centers[Face::F as usize] != Face::F {
+            cube.turn_extended(&ExtendedMove::Rotation(Axis::Y, 1));
+        }
+        cube
+    }
+
+    /**Translates turns solving `self.reoriented()` into turns solving `self`, by turning
+     * the face that holds the same center instead.
+     */
+    pub(crate) fn turns_from_reoriented(&self, turns: &[Move]) -> Vec<Move> {
+        turns.iter()
+            .map(|turn| {
+                let pos = FACES.iter().find(|pos| self.get_center(pos) == &turn.face()).unwrap();
+                Move::from_face_and_amount(*pos, turn.amount()).unwrap()
+            })
+            .collect()
+    }
+
     pub fn turn(&mut self, turn: &Move) -> &mut Self {
         match turn {
             Move::U  => self.u(),
@@ -142,6 +599,78 @@ impl Cube {
         self
     }
 
+    pub fn turn_extended(&mut self, turn: &ExtendedMove) -> &mut Self {
+        let (face, amount, in_layers): (Face, u8, LayerFilter) = match *turn {
+            ExtendedMove::Face(turn) => return self.turn(&turn),
+            ExtendedMove::Slice(slice, amount) => (
+                slice.face(), amount,
+                |face, facelets| !facelets.contains(&face) && !facelets.contains(&face.opposite()),
+            ),
+            ExtendedMove::Wide(face, amount) => (
+                face, amount,
+                |face, facelets| !facelets.contains(&face.opposite()),
+            ),
+            ExtendedMove::Rotation(axis, amount) => (axis.face(), amount, |_, _| true),
+        };
+        for _ in 0..amount % 4 {
+            self.quarter_turn_layers(face, |facelets| in_layers(face, facelets));
+        }
+        self
+    }
+
+    pub fn apply_extended_moves(&mut self, moves: &[ExtendedMove]) -> &mut Self {
+        for turn in moves {
+            self.turn_extended(turn);
+        }
+        self
+    }
+
+    /**Turns every piece (including centers) whose facelets satisfy `in_layers` a clockwise
+     * quarter turn around the axis through `face`. This is slower than the dedicated face 
+     * turns, but works for any combination of layers.
+     */
+    fn quarter_turn_layers(&mut self, face: Face, in_layers: impl Fn(&[Face]) -> bool) {
+        let old = *self;
+
+        for pos in FACES {
+            if in_layers(&[pos]) {
+                self.centers[face.quarter_turn(pos) as usize] = old.centers[pos as usize];
+            }
+        }
+
+        for (i, facelets) in EDGE_FACELETS.iter().enumerate() {
+            if !in_layers(facelets) {
+                continue
+            }
+            let image = facelets.map(|f| face.quarter_turn(f));
+            let j = EDGE_FACELETS.iter()
+                .position(|other| other.contains(&image[0]) && other.contains(&image[1]))
+                .unwrap();
+            // The reference facelet is moved to index `shift` of the new position
+            let shift = EDGE_FACELETS[j].iter().position(|&f| f == image[0]).unwrap() as u8;
+
+            let mut cubie = old.edges[i];
+            cubie.orientation ^= shift;
+            self.edges[j] = cubie;
+        }
+
+        for (i, facelets) in CORNER_FACELETS.iter().enumerate() {
+            if !in_layers(facelets) {
+                continue
+            }
+            let image = facelets.map(|f| face.quarter_turn(f));
+            let j = CORNER_FACELETS.iter()
+                .position(|other| image.iter().all(|f| other.contains(f)))
+                .unwrap();
+            // The reference facelet is moved `shift` steps clockwise of the new reference facelet
+            let shift = CORNER_FACELETS[j].iter().position(|&f| f == image[0]).unwrap() as u8;
+
+            let mut cubie = old.corners[i];
+            cubie.rotate((3 - shift) % 3);
+            self.corners[j] = cubie;
+        }
+    }
+
     fn u(&mut self) -> &mut Self {
         self.edges.swap(Edge::UR as usize, Edge::UB as usize);
         self.edges.swap(Edge::UB as usize, Edge::UL as usize);
@@ -245,6 +774,79 @@ impl Cube {
     }
 }
 
+impl std::ops::Mul for Cube {
+    type Output = Cube;
+
+    /// `a * b` is the state of applying `a` and then `b`, see `Cube::compose`.
+    fn mul(self, other: Cube) -> Cube {
+        self.compose(&other)
+    }
+}
+
+impl std::ops::Mul<&Cube> for &Cube {
+    type Output = Cube;
+
+    fn mul(self, other: &Cube) -> Cube {
+        self.compose(other)
+    }
+}
+
+impl std::ops::MulAssign for Cube {
+    fn mul_assign(&mut self, other: Cube) {
+        *self = self.compose(&other);
+    }
+}
+
+impl std::ops::Neg for Cube {
+    type Output = Cube;
+
+    /// `-a` is the inverse of `a`, see `Cube::inverse`.
+    fn neg(self) -> Cube {
+        self.inverse()
+    }
+}
+
+impl std::ops::Neg for &Cube {
+    type Output = Cube;
+
+    fn neg(self) -> Cube {
+        self.inverse()
+    }
+}
+
+/**Whether `centers` is a rotation of the centers on a solved cube. That is the case if
+ * opposite centers are on opposite faces, and the centers around the URF corner are in
+ * the same cyclic order as the faces of some corner.
+ */
+pub(crate) fn centers_form_rotation(centers: &[Face; 6]) -> bool {
+    let opposites_match = FACES.iter()
+        .all(|&face| centers[face.opposite() as usize] == centers[face as usize].opposite());
+    let urf = [Face::U, Face::R, Face::F].map(|face| centers[face as usize]);
+    let handedness_matches = CORNER_FACELETS.iter()
+        .any(|facelets| (0..3).any(|k| (0..3).all(|i| facelets[(i + k) % 3] == urf[i])));
+    return opposites_match && handedness_matches
+}
+
+/**The position, piece and orientation of a cubie after conjugating by `symmetry`, given the
+ * facelets of every position. Sticker `j` of a piece with orientation `o` is on facelet
+ * `j - o` (mod N) of its position.
+ */
+fn conjugate_cubie<const N: usize>(facelets: &[[Face; N]], symmetry: &Symmetry, pos: usize, piece: usize, orientation: u8) -> (usize, usize, u8) {
+    let find = |image: &[Face; N]| facelets.iter()
+        .position(|other| image.iter().all(|f| other.contains(f)))
+        .unwrap();
+    let pos_image = facelets[pos].map(|f| symmetry.face(f));
+    let piece_image = facelets[piece].map(|f| symmetry.face(f));
+    let (new_pos, new_piece) = (find(&pos_image), find(&piece_image));
+
+    // The reference sticker of the new piece is sticker `j` of the old piece, which is on
+    // facelet `k` of the old position, which is facelet `new_k` of the new position
+    let j = piece_image.iter().position(|&f| f == facelets[new_piece][0]).unwrap();
+    let k = (j + N - orientation as usize) % N;
+    let new_k = facelets[new_pos].iter().position(|&f| f == pos_image[k]).unwrap();
+    return (new_pos, new_piece, ((N - new_k) % N) as u8)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -265,7 +867,7 @@ mod tests {
         cube.corners[Corner::ULF as usize].rotate(2);
         cube.corners[Corner::URB as usize].rotate(2);
 
-        assert_eq!(cube, Cube::new());
+        assert_eq!(cube, Cube::new(), "\n{}", cube);
     }
 
     #[test]
@@ -277,7 +879,6 @@ mod tests {
         for turn in &moves {
             cube.turn(turn);
         }
-        dbg!(&cube.edges);
 
         cube.corners.swap(Corner::DLF as usize, Corner::DRF as usize);
         cube.corners.swap(Corner::DRF as usize, Corner::DRB as usize);
@@ -285,7 +886,148 @@ mod tests {
         cube.corners[Corner::DLF as usize].rotate(2);
         cube.corners[Corner::DRB as usize].rotate(2);
 
-        assert_eq!(cube, Cube::new());
+        assert_eq!(cube, Cube::new(), "\n{}", cube);
+    }
+
+    #[test]
+    fn layer_turns_match_face_turns() {
+        let faces = [(Face::U, Move::U), (Face::L, Move::L), (Face::D, Move::D), (Face::R, Move::R), (Face::F, Move::F), (Face::B, Move::B)];
+        for (face, turn) in faces {
+            let mut cube1 = Cube::new();
+            let mut cube2 = Cube::new();
+            cube1.apply_moves(&[Move::R, Move::U, Move::F, Move::L, Move::D, Move::B]);
+            cube2.apply_moves(&[Move::R, Move::U, Move::F, Move::L, Move::D, Move::B]);
+            
+            cube1.turn(&turn);
+            cube2.quarter_turn_layers(face, |facelets| facelets.contains(&face));
+            assert_eq!(cube1, cube2, "\n{}\n\n{}", cube1, cube2);
+        }
+    }
+
+    #[test]
+    fn extended_moves() {
+        // A slice turn is two face turns and a rotation
+        let mut cube1 = Cube::new();
+        let mut cube2 = Cube::new();
+        cube1.turn_extended(&ExtendedMove::Slice(Slice::M, 1));
+        cube2.apply_moves(&[Move::R, Move::Lp]).turn_extended(&ExtendedMove::Rotation(Axis::X, 3));
+        assert_eq!(cube1, cube2, "\n{}\n\n{}", cube1, cube2);
+
+        // A wide turn is a face turn and a rotation
+        let mut cube1 = Cube::new();
+        let mut cube2 = Cube::new();
+        cube1.turn_extended(&ExtendedMove::Wide(Face::U, 1));
+        cube2.apply_moves(&[Move::D]).turn_extended(&ExtendedMove::Rotation(Axis::Y, 1));
+        assert_eq!(cube1, cube2, "\n{}\n\n{}", cube1, cube2);
+        assert!(!cube1.is_solved());
+
+        // Rotations move the centers, but leave the cube solved
+        let mut cube = Cube::new();
+        cube.apply_extended_moves(&[ExtendedMove::Rotation(Axis::X, 1), ExtendedMove::Rotation(Axis::Z, 1)]);
+        assert_eq!(cube.get_center(&Face::U), &Face::L);
+        assert_eq!(cube.get_center(&Face::R), &Face::F);
+        assert!(cube.is_solved());
+        assert_eq!(cube.reoriented(), Cube::new());
+
+        // Each extended move has order 4
+        for turn in [ExtendedMove::Slice(Slice::E, 1), ExtendedMove::Slice(Slice::S, 1), ExtendedMove::Wide(Face::B, 1)] {
+            let mut cube = Cube::new();
+            cube.turn_extended(&turn);
+            assert_ne!(cube, Cube::new());
+            cube.turn_extended(&turn).turn_extended(&turn).turn_extended(&turn);
+            assert_eq!(cube, Cube::new(), "\n{}", cube);
+        }
+    }
+
+    #[test]
+    fn validation() {
+        let mut cube = Cube::new();
+        cube.apply_moves(&[Move::R, Move::U, Move::Fp, Move::L2, Move::B]);
+        cube.turn_extended(&ExtendedMove::Rotation(Axis::Y, 1));
+        assert_eq!(cube.validate(), Ok(()));
+
+        let mut twisted = cube;
+        twisted.corners[Corner::ULB as usize].rotate(1);
+        assert_eq!(twisted.validate(), Err(CubeError::TwistedCorner { twist: 1 }));
+
+        let mut flipped = cube;
+        flipped.edges[Edge::DB as usize].flip();
+        assert_eq!(flipped.validate(), Err(CubeError::FlippedEdge));
+
+        let mut swapped = cube;
+        swapped.edges.swap(Edge::UF as usize, Edge::UB as usize);
+        assert_eq!(swapped.validate(), Err(CubeError::Parity));
+        swapped.corners.swap(Corner::URF as usize, Corner::URB as usize);
+        assert_eq!(swapped.validate(), Ok(()));
+
+        let mut duplicate = Cube::new();
+        duplicate.edges[Edge::UF as usize].piece_type = Edge::UR;
+        assert_eq!(duplicate.validate(), Err(CubeError::DuplicateEdge { duplicate: Edge::UR, missing: Edge::UF }));
+
+        let mut centers = Cube::new();
+        centers.centers.swap(Face::U as usize, Face::D as usize);
+        assert_eq!(centers.validate(), Err(CubeError::InvalidCenters));
+    }
+
+    #[test]
+    fn group_operations() {
+        let moves1 = [Move::R, Move::U, Move::Fp, Move::L2, Move::B, Move::Dp];
+        let moves2 = [Move::F, Move::R2, Move::Up, Move::B2, Move::L];
+        let mut a = Cube::new();
+        a.apply_moves(&moves1);
+        let mut b = Cube::new();
+        b.apply_moves(&moves2).turn_extended(&ExtendedMove::Rotation(Axis::Z, 1));
+
+        let mut ab = Cube::new();
+        ab.apply_moves(&moves1).apply_moves(&moves2).turn_extended(&ExtendedMove::Rotation(Axis::Z, 1));
+        assert_eq!(a * b, ab);
+        assert_eq!(a * -a, Cube::new());
+        assert_eq!(-b * b, Cube::new());
+        assert_eq!(-(a * b), -b * -a);
+        assert_eq!((a * b) * a, a * (b * a));
+
+        // Undoing the moves gives the inverse
+        let mut inverse = Cube::new();
+        for turn in moves1.iter().rev() {
+            inverse.turn(&turn.inverse());
+        }
+        assert_eq!(a.inverse(), inverse);
+
+        // The commutator of two disjoint moves is the identity
+        let mut u = Cube::new();
+        u.turn(&Move::U);
+        let mut d = Cube::new();
+        d.turn(&Move::D);
+        assert_eq!(u * d * -u * -d, Cube::new());
+
+        let mut twisted = a;
+        twisted.corners[0].rotate(1);
+        assert!(twisted.same_permutation(&a));
+        assert!(!twisted.same_permutation(&b));
+        let mut rotation = Cube::new();
+        rotation.turn_extended(&ExtendedMove::Rotation(Axis::Y, 1));
+        assert!(a.equivalent(&(a * rotation)));
+        assert!(!a.equivalent(&(a * u)));
+    }
+
+    #[test]
+    fn conjugation() {
+        let symmetries = Symmetry::all();
+        assert_eq!(symmetries.len(), 48);
+        assert_eq!(symmetries.iter().filter(|symmetry| symmetry.is_reflection()).count(), 24);
+        assert_eq!(Symmetry::preserving_ud_axis().len(), 16);
+        assert_eq!(symmetries[0].faces, FACES);
+
+        let moves = [Move::R, Move::U, Move::Fp, Move::L2, Move::B, Move::Dp, Move::F];
+        let mut cube = Cube::new();
+        cube.apply_moves(&moves).turn_extended(&ExtendedMove::Rotation(Axis::X, 1));
+        for symmetry in &symmetries {
+            let mut conjugated = Cube::new();
+            conjugated.apply_moves(&moves.map(|turn| symmetry.turn(&turn)));
+            assert_eq!(cube.reoriented().conjugate(symmetry), conjugated);
+            assert_eq!(cube.conjugate(symmetry).conjugate(&symmetry.inverse()), cube);
+            assert_eq!(cube.conjugate(symmetry).validate(), Ok(()));
+        }
     }
 
     #[test]
@@ -294,7 +1036,7 @@ mod tests {
         let mut cube1 = Cube::new();
         let mut cube2 = Cube::new();
         
-        let moves = vec![Move::R2, Move::U2].repeat(3);
+        let moves = [Move::R2, Move::U2].repeat(3);
         for turn in moves {
             cube1.turn(&turn);
         }
@@ -302,9 +1044,9 @@ mod tests {
         cube2.edges.swap(Edge::UF as usize, Edge::UB as usize);
         cube2.edges.swap(Edge::RF as usize, Edge::RB as usize);
 
-        assert_eq!(cube1, cube2);
+        assert_eq!(cube1, cube2, "\n{}\n\n{}", cube1, cube2);
 
-        let moves = vec![Move::L2, Move::F2].repeat(3);
+        let moves = [Move::L2, Move::F2].repeat(3);
         for turn in moves {
             cube1.turn(&turn);
         }
@@ -312,9 +1054,9 @@ mod tests {
         cube2.edges.swap(Edge::UF as usize, Edge::DF as usize);
         cube2.edges.swap(Edge::UL as usize, Edge::DL as usize);
 
-        assert_eq!(cube1, cube2);
+        assert_eq!(cube1, cube2, "\n{}\n\n{}", cube1, cube2);
 
-        let moves = vec![Move::D2, Move::B2].repeat(3);
+        let moves = [Move::D2, Move::B2].repeat(3);
         for turn in moves {
             cube1.turn(&turn);
         }
@@ -322,6 +1064,6 @@ mod tests {
         cube2.edges.swap(Edge::RB as usize, Edge::LB as usize);
         cube2.edges.swap(Edge::DR as usize, Edge::DL as usize);
 
-        assert_eq!(cube1, cube2);
+        assert_eq!(cube1, cube2, "\n{}\n\n{}", cube1, cube2);
     }
 }
\ No newline at end of file
@@ -0,0 +1,167 @@
+use super::cube::{Cube, Edge, Corner, Face, EDGES, CORNERS, FACES};
+use std::fmt;
+
+/**Pieces moved in a cycle: the piece at `pieces[i]` moves to `pieces[i + 1]`, and the last
+ * piece moves to the first. `orientation` is the total change in orientation of the pieces
+ * after going once around the cycle, so a piece in a cycle of length `n` only returns to
+ * its original state after `n` repetitions if `orientation` is 0.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle<T> {
+    pub pieces: Vec<T>,
+    pub orientation: u8,
+}
+
+/**The cycle decomposition of a cube state. Pieces that are neither moved nor twisted/flipped
+ * are left out. As a string, each cycle is written like `(URF ULB DRB)`, followed by `+` if
+ * the corners are twisted clockwise in total, `-` if counter-clockwise, and for edges `+` if
+ * they are flipped.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycles {
+    pub corners: Vec<Cycle<Corner>>,
+    pub edges: Vec<Cycle<Edge>>,
+    /// Only non-empty if the cube has been rotated as a whole.
+    pub centers: Vec<Cycle<Face>>,
+}
+
+impl Cycles {
+    /// Number of repetitions needed to return every piece to its original state.
+    pub fn order(&self) -> usize {
+        let corners = self.corners.iter()
+            .map(|cycle| cycle.pieces.len() * if cycle.orientation == 0 { 1 } else { 3 });
+        let edges = self.edges.iter()
+            .map(|cycle| cycle.pieces.len() * if cycle.orientation == 0 { 1 } else { 2 });
+        let centers = self.centers.iter().map(|cycle| cycle.pieces.len());
+        corners.chain(edges).chain(centers).fold(1, lcm)
+    }
+}
+
+impl Cube {
+    pub fn cycles(&self) -> Cycles {
+        Cycles {
+            corners: cycles(&CORNERS, |corner| *self.get_corner_position(corner), |pos| self.get_corner_orientation(pos), 3),
+            edges: cycles(&EDGES, |edge| *self.get_edge_position(edge), |pos| self.get_edge_orientation(pos), 2),
+            centers: cycles(&FACES, |face| *FACES.iter().find(|pos| self.get_center(pos) == face).unwrap(), |_| 0, 1),
+        }
+    }
+
+    /// The order of the state in the cube group, i.e. the smallest `n > 0` with `self`^`n` solved.
+    pub fn order(&self) -> usize {
+        self.cycles().order()
+    }
+}
+
+/**Cycle decomposition of the permutation sending each piece to `position(piece)`, summing
+ * the `orientation` at each position of a cycle modulo `modulus`.
+ */
+fn cycles<T: Copy + PartialEq>(
+    pieces: &[T],
+    position: impl Fn(&T) -> T,
+    orientation: impl Fn(&T) -> u8,
+    modulus: u8,
+) -> Vec<Cycle<T>> {
+    let mut visited: Vec<T> = Vec::new();
+    let mut result = Vec::new();
+    for start in pieces {
+        if visited.contains(start) {
+            continue
+        }
+        let mut cycle = Cycle { pieces: vec![*start], orientation: 0 };
+        let mut piece = position(start);
+        cycle.orientation = orientation(&piece);
+        while piece != *start {
+            cycle.pieces.push(piece);
+            piece = position(&piece);
+            cycle.orientation = (cycle.orientation + orientation(&piece)) % modulus;
+        }
+        visited.extend(cycle.pieces.iter());
+        if cycle.pieces.len() > 1 || cycle.orientation != 0 {
+            result.push(cycle);
+        }
+    }
+    result
+}
+
+fn lcm(a: usize, b: usize) -> usize {
+    let (mut x, mut y) = (a, b);
+    while y != 0 {
+        (x, y) = (y, x % y);
+    }
+    a / x * b
+}
+
+fn write_cycles<T: fmt::Debug>(
+    f: &mut fmt::Formatter<'_>,
+    cycles: &[Cycle<T>],
+    suffix: fn(u8) -> &'static str,
+    first: &mut bool,
+) -> fmt::Result {
+    for cycle in cycles {
+        if !*first {
+            write!(f, " ")?;
+        }
+        *first = false;
+        let pieces: Vec<String> = cycle.pieces.iter().map(|piece| format!("{:?}", piece)).collect();
+        write!(f, "({}){}", pieces.join(" "), suffix(cycle.orientation))?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for Cycles {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut first = true;
+        // A corner with orientation 2 has its U/D sticker turned one step clockwise
+        write_cycles(f, &self.corners, |o| match o { 0 => "", 1 => "-", _ => "+" }, &mut first)?;
+        write_cycles(f, &self.edges, |o| if o == 0 { "" } else { "+" }, &mut first)?;
+        write_cycles(f, &self.centers, |_| "", &mut first)?;
+        if first {
+            write!(f, "()")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::cube::{Axis, ExtendedMove};
+    use super::super::sequence::MoveSequence;
+
+    fn cube(moves: &str) -> Cube {
+        let mut cube = Cube::new();
+        cube.apply_moves(&moves.parse::<MoveSequence>().unwrap());
+        cube
+    }
+
+    #[test]
+    fn cycle_notation() {
+        assert_eq!(Cube::new().cycles().to_string(), "()");
+        assert_eq!(cube("U").cycles().to_string(), "(URF ULF ULB URB) (UR UF UL UB)");
+        assert_eq!(cube("R2 U2").cycles().to_string(), "(URF DRB ULB) (URB DRF ULF) (UR DR UL) (UB UF) (RF RB)");
+        assert_eq!(cube("F").cycles().to_string(), "(URF DRF DLF ULF) (UF RF DF LF)");
+
+        assert_eq!(cube("R U R' U R U2 R'").cycles().to_string(), "(URF ULB)- (URB ULF)+ (UR UB UL)");
+
+        let mut rotated = Cube::new();
+        rotated.turn_extended(&ExtendedMove::Rotation(Axis::Y, 2));
+        assert_eq!(rotated.cycles().centers.len(), 2);
+    }
+
+    #[test]
+    fn orders() {
+        assert_eq!(Cube::new().order(), 1);
+        assert_eq!(cube("U").order(), 4);
+        assert_eq!(cube("R U").order(), 105);
+        assert_eq!(cube("R U R' U'").order(), 6);
+        assert_eq!(cube("R U2 D' B D'").order(), 1260);
+
+        let sune = cube("R U R' U R U2 R'");
+        assert_eq!(sune.order(), 6);
+        let mut repeated = Cube::new();
+        for _ in 0..6 {
+            repeated *= sune;
+        }
+        assert!(repeated.is_solved());
+    }
+}
@@ -0,0 +1,302 @@
+use super::cube::{centers_form_rotation, Cube, CubeError, Edge, Corner, Face, EDGES, CORNERS, FACES, EDGE_FACELETS, CORNER_FACELETS};
+use std::fmt;
+use std::str::FromStr;
+
+/**Indices of the facelets of each edge position, in the same order as `EDGE_FACELETS`.
+ * Facelets are numbered as in Kociemba's facelet string: the faces U, R, F, D, L, B
+ * each with 9 facelets read row by row.
+ */
+const EDGE_INDICES: [[usize; 2]; 12] = [
+    [5, 10], [1, 46], [3, 37], [7, 19],
+    [23, 12], [48, 14], [50, 39], [21, 41],
+    [32, 16], [34, 52], [30, 43], [28, 25],
+];
+
+/// Indices of the facelets of each corner position, in the same order as `CORNER_FACELETS`.
+const CORNER_INDICES: [[usize; 3]; 8] = [
+    [8, 9, 20], [2, 45, 11], [0, 36, 47], [6, 18, 38],
+    [35, 17, 51], [29, 26, 15], [27, 44, 24], [33, 53, 42],
+];
+
+/// The faces in the order they appear in a facelet string.
+const FACELET_ORDER: [Face; 6] = [Face::U, Face::R, Face::F, Face::D, Face::L, Face::B];
+
+fn center_index(face: Face) -> usize {
+    FACELET_ORDER.iter().position(|&f| f == face).unwrap() * 9 + 4
+}
+
+/**The cube as 54 stickers. Each sticker holds the colour of a center, named by the face
+ * that center belongs to on a solved cube. As a string, the stickers of the faces U, R, F,
+ * D, L and B are listed in turn, each face read row by row as seen from the outside with
+ * U (or F for the U and D faces) at the top, e.g. a solved cube is
+ * `UUUUUUUUURRRRRRRRRFFFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB`.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FaceletCube {
+    facelets: [Face; 54],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FaceletError {
+    /// The string does not have exactly 54 characters.
+    InvalidLength(usize),
+    /// A character that is not one of `URFDLB`.
+    InvalidCharacter { position: usize, character: char },
+    /// A colour does not appear on exactly 9 stickers.
+    ColourCount { colour: Face, count: usize },
+    /// The stickers on the edge at this position do not belong to any edge.
+    InvalidEdge(Edge),
+    /// The stickers on the corner at this position do not belong to any corner.
+    InvalidCorner(Corner),
+    /// The stickers make up a cube that cannot be reached by turning a solved cube.
+    Unsolvable(CubeError),
+}
+
+impl fmt::Display for FaceletError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FaceletError::InvalidLength(length) => write!(f, "expected 54 facelets, got {}", length),
+            FaceletError::InvalidCharacter { position, character } => {
+                write!(f, "invalid facelet `{}` at position {}", character, position)
+            },
+            FaceletError::ColourCount { colour, count } => {
+                write!(f, "colour {:?} appears on {} facelets instead of 9", colour, count)
+            },
+            FaceletError::InvalidEdge(pos) => write!(f, "the stickers at edge {:?} do not form an edge", pos),
+            FaceletError::InvalidCorner(pos) => write!(f, "the stickers at corner {:?} do not form a corner", pos),
+            FaceletError::Unsolvable(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for FaceletError {}
+
+impl From<CubeError> for FaceletError {
+    fn from(error: CubeError) -> Self {
+        FaceletError::Unsolvable(error)
+    }
+}
+
+impl FaceletCube {
+    pub fn get(&self, index: usize) -> Face {
+        self.facelets[index]
+    }
+
+    pub fn facelets(&self) -> &[Face; 54] {
+        &self.facelets
+    }
+
+    pub fn from_cube(cube: &Cube) -> Self {
+        let mut facelets = [Face::U; 54];
+
+        for face in FACES {
+            facelets[center_index(face)] = *cube.get_center(&face);
+        }
+
+        // Sticker `k` of a piece with orientation `o` is on facelet `k + o` of the position
+        // for edges, and `k - o` (counting clockwise) for corners.
+        for pos in EDGES {
+            let edge = *cube.get_edge_type(&pos) as usize;
+            let orientation = cube.get_edge_orientation(&pos) as usize;
+            for (k, colour) in EDGE_FACELETS[edge].iter().enumerate() {
+                facelets[EDGE_INDICES[pos as usize][(k + orientation) % 2]] = *colour;
+            }
+        }
+        for pos in CORNERS {
+            let corner = *cube.get_corner_type(&pos) as usize;
+            let orientation = cube.get_corner_orientation(&pos) as usize;
+            for (k, colour) in CORNER_FACELETS[corner].iter().enumerate() {
+                facelets[CORNER_INDICES[pos as usize][(k + 3 - orientation) % 3]] = *colour;
+            }
+        }
+
+        Self { facelets }
+    }
+
+    /// The cubie representation of the stickers, if they can be on a cube reachable by turns.
+    pub fn to_cube(&self) -> Result<Cube, FaceletError> {
+        for colour in FACES {
+            let count = self.facelets.iter().filter(|&&c| c == colour).count();
+            if count != 9 {
+                return Err(FaceletError::ColourCount { colour, count })
+            }
+        }
+
+        let centers = FACES.map(|face| self.facelets[center_index(face)]);
+        if !centers_form_rotation(&centers) {
+            return Err(CubeError::InvalidCenters.into())
+        }
+
+        let mut edges = [(Edge::UR, 0); 12];
+        for pos in EDGES {
+            let colours = EDGE_INDICES[pos as usize].map(|i| self.facelets[i]);
+            edges[pos as usize] = match_edge(&colours).ok_or(FaceletError::InvalidEdge(pos))?;
+        }
+
+        let mut corners = [(Corner::URF, 0); 8];
+        for pos in CORNERS {
+            let colours = CORNER_INDICES[pos as usize].map(|i| self.facelets[i]);
+            corners[pos as usize] = match_corner(&colours).ok_or(FaceletError::InvalidCorner(pos))?;
+        }
+
+        let cube = Cube::from_pieces(edges, corners, centers);
+        cube.validate()?;
+        Ok(cube)
+    }
+}
+
+/// The edge with stickers `colours` on the facelets of a position, and its orientation.
+fn match_edge(colours: &[Face; 2]) -> Option<(Edge, u8)> {
+    for edge in EDGES {
+        let home = EDGE_FACELETS[edge as usize];
+        for orientation in 0..2 {
+            if (0..2).all(|k| colours[(k + orientation) % 2] == home[k]) {
+                return Some((edge, orientation as u8))
+            }
+        }
+    }
+    None
+}
+
+/// The corner with stickers `colours` on the facelets of a position, and its orientation.
+fn match_corner(colours: &[Face; 3]) -> Option<(Corner, u8)> {
+    for corner in CORNERS {
+        let home = CORNER_FACELETS[corner as usize];
+        for orientation in 0..3 {
+            if (0..3).all(|k| colours[(k + 3 - orientation) % 3] == home[k]) {
+                return Some((corner, orientation as u8))
+            }
+        }
+    }
+    None
+}
+
+impl From<&Cube> for FaceletCube {
+    fn from(cube: &Cube) -> Self {
+        Self::from_cube(cube)
+    }
+}
+
+impl TryFrom<&FaceletCube> for Cube {
+    type Error = FaceletError;
+
+    fn try_from(facelets: &FaceletCube) -> Result<Self, Self::Error> {
+        facelets.to_cube()
+    }
+}
+
+impl fmt::Display for FaceletCube {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for face in self.facelets {
+            let c = match face {
+                Face::U => 'U',
+                Face::R => 'R',
+                Face::F => 'F',
+                Face::D => 'D',
+                Face::L => 'L',
+                Face::B => 'B',
+            };
+            write!(f, "{}", c)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for FaceletCube {
+    type Err = FaceletError;
+
+    /// Parses the stickers. Any string of 54 characters from `URFDLB` is accepted.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let length = s.chars().count();
+        if length != 54 {
+            return Err(FaceletError::InvalidLength(length))
+        }
+
+        let mut facelets = [Face::U; 54];
+        for (position, character) in s.chars().enumerate() {
+            facelets[position] = match character {
+                'U' => Face::U,
+                'R' => Face::R,
+                'F' => Face::F,
+                'D' => Face::D,
+                'L' => Face::L,
+                'B' => Face::B,
+                _ => return Err(FaceletError::InvalidCharacter { position, character }),
+            };
+        }
+        Ok(Self { facelets })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::cube::{ExtendedMove, Axis, Slice};
+    use super::super::cube::Move::*;
+    use super::super::test_fixtures::scrambled;
+
+    const SOLVED: &str = "UUUUUUUUURRRRRRRRRFFFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB";
+
+    #[test]
+    fn facelets_of_moves() {
+        assert_eq!(FaceletCube::from(&Cube::new()).to_string(), SOLVED);
+
+        let mut cube = Cube::new();
+        cube.turn(&R);
+        assert_eq!(FaceletCube::from(&cube).to_string(), "UUFUUFUUFRRRRRRRRRFFDFFDFFDDDBDDBDDBLLLLLLLLLUBBUBBUBB");
+
+        let mut cube = Cube::new();
+        cube.turn(&F);
+        assert_eq!(FaceletCube::from(&cube).to_string(), "UUUUUULLLURRURRURRFFFFFFFFFRRRDDDDDDLLDLLDLLDBBBBBBBBB");
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut cube = scrambled();
+        let facelets = FaceletCube::from(&cube);
+        assert_eq!(facelets.to_cube(), Ok(cube));
+
+        cube.apply_extended_moves(&[ExtendedMove::Slice(Slice::E, 1), ExtendedMove::Rotation(Axis::X, 1)]);
+        assert_eq!(FaceletCube::from(&cube).to_cube(), Ok(cube));
+        cube.turn_extended(&ExtendedMove::Rotation(Axis::Y, 3));
+        let facelets = FaceletCube::from(&cube);
+        assert_eq!(facelets.to_cube(), Ok(cube));
+        assert_eq!(facelets.to_string().parse::<FaceletCube>(), Ok(facelets));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!("UUU".parse::<FaceletCube>(), Err(FaceletError::InvalidLength(3)));
+        assert_eq!(
+            SOLVED.replacen('R', "X", 1).parse::<FaceletCube>(),
+            Err(FaceletError::InvalidCharacter { position: 9, character: 'X' })
+        );
+
+        let check = |s: &str| s.parse::<FaceletCube>().unwrap().to_cube();
+        assert_eq!(check(&SOLVED.replacen('R', "U", 1)), Err(FaceletError::ColourCount { colour: Face::U, count: 10 }));
+
+        // Swap the colours of the U and D faces
+        let mirrored = SOLVED.replace('U', "x").replace('D', "U").replace('x', "D");
+        assert_eq!(check(&mirrored), Err(FaceletError::Unsolvable(CubeError::InvalidCenters)));
+
+        // Flip the UR edge, twist the URF corner, and swap two stickers of different pieces
+        let mut facelets: Vec<char> = SOLVED.chars().collect();
+        facelets.swap(5, 10);
+        assert_eq!(check(&facelets.iter().collect::<String>()), Err(FaceletError::Unsolvable(CubeError::FlippedEdge)));
+
+        let mut facelets: Vec<char> = SOLVED.chars().collect();
+        facelets.swap(8, 9);
+        facelets.swap(9, 20);
+        assert_eq!(check(&facelets.iter().collect::<String>()), Err(FaceletError::Unsolvable(CubeError::TwistedCorner { twist: 1 })));
+
+        let mut facelets: Vec<char> = SOLVED.chars().collect();
+        facelets.swap(10, 28);
+        assert_eq!(check(&facelets.iter().collect::<String>()), Err(FaceletError::InvalidEdge(Edge::UR)));
+
+        // Swap two edges
+        let mut facelets: Vec<char> = SOLVED.chars().collect();
+        facelets.swap(5, 7);
+        facelets.swap(10, 19);
+        assert_eq!(check(&facelets.iter().collect::<String>()), Err(FaceletError::Unsolvable(CubeError::Parity)));
+    }
+}
@@ -0,0 +1,7 @@
+use super::coordinates;
+use super::cube;
+
+mod solver;
+mod tables;
+
+pub use solver::{solve, KociembaSolver, SearchLimits, SolveError};
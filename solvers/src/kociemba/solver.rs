@@ -0,0 +1,333 @@
+use super::coordinates::{
+    Coordinate, CornerOrientation, CornerPermutation, EdgeOrientation, ESliceCombination, ESlicePermutation,
+    UDEdgePermutation,
+};
+use super::tables::{Tables, PHASE2_MOVES};
+use super::cube::{Cube, CubeError, Move, MOVES};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveError {
+    /// The cube cannot be reached by turning a solved cube, so it cannot be solved either.
+    InvalidCube(CubeError),
+}
+
+impl std::fmt::Display for SolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolveError::InvalidCube(error) => write!(f, "the cube is not solvable: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for SolveError {}
+
+impl From<CubeError> for SolveError {
+    fn from(error: CubeError) -> Self {
+        SolveError::InvalidCube(error)
+    }
+}
+
+/// When to stop searching for shorter solutions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchLimits {
+    /// Return as soon as a solution of at most this many moves is found.
+    pub target_length: usize,
+    /**Return the shortest solution found so far once this much time has passed. The search
+     * always runs until the first solution is found, which takes well under a millisecond.
+     */
+    pub time_budget: Option<Duration>,
+}
+
+impl Default for SearchLimits {
+    fn default() -> Self {
+        Self { target_length: 20, time_budget: Some(Duration::from_secs(1)) }
+    }
+}
+
+/**Solver using Kociemba's two-phase algorithm. Phase 1 brings the cube into the subgroup
+ * <U, D, R2, L2, F2, B2>, where all pieces are oriented and the E-slice edges are in the
+ * E-slice, and phase 2 solves the cube using only moves of that subgroup. Both phases are
+ * searched with IDA* guided by pruning tables, and phase 1 solutions of increasing length
+ * are tried until the combined solution is short enough.
+ *
+ * Creating the solver builds six move tables and four pruning tables over pairs of
+ * coordinates, which takes a fraction of a second in release builds. The search itself reads
+ * the tables only, so one solver can serve any number of `solve` calls, also from several
+ * threads at once.
+ *
+ * Unlike the tables of the other solvers, these are not kept in a `TableStore`. A store holds
+ * the `LookupTable` of a `Stage`, giving the distance to solved of a single index, while these
+ * are move tables and pruning tables over pairs of coordinates. Building them is also cheap
+ * enough that saving them would not noticeably shorten the start of a process.
+ */
+pub struct KociembaSolver {
+    tables: Tables,
+    limits: SearchLimits,
+}
+
+impl KociembaSolver {
+    pub fn new(limits: SearchLimits) -> Self {
+        Self { tables: Tables::new(), limits }
+    }
+
+    /**Finds a sequence of face turns solving `cube` within the limits of the solver. If the
+     * cube has been rotated as a whole, the turns refer to the faces in their current position.
+     */
+    pub fn solve(&self, cube: &Cube) -> Result<Vec<Move>, SolveError> {
+        self.solve_with_limits(cube, &self.limits)
+    }
+
+    /// Like `solve`, but overriding the limits given when creating the solver.
+    pub fn solve_with_limits(&self, cube: &Cube, limits: &SearchLimits) -> Result<Vec<Move>, SolveError> {
+        cube.validate()?;
+
+        let home = cube.reoriented();
+        let mut search = Search {
+            tables: &self.tables,
+            cube: home,
+            limits: *limits,
+            deadline: limits.time_budget.map(|budget| Instant::now() + budget),
+            moves: Vec::new(),
+            best: None,
+        };
+        search.run();
+        Ok(cube.turns_from_reoriented(&search.best.unwrap()))
+    }
+}
+
+impl Default for KociembaSolver {
+    fn default() -> Self {
+        Self::new(SearchLimits::default())
+    }
+}
+
+/// State of a single two-phase search.
+struct Search<'a> {
+    tables: &'a Tables,
+    cube: Cube,
+    limits: SearchLimits,
+    deadline: Option<Instant>,
+    /// The moves leading to the node currently visited.
+    moves: Vec<Move>,
+    best: Option<Vec<Move>>,
+}
+
+impl Search<'_> {
+    fn run(&mut self) {
+        let twist = CornerOrientation::coordinate(&self.cube);
+        let flip = EdgeOrientation::coordinate(&self.cube);
+        let slice = ESliceCombination::coordinate(&self.cube);
+
+        let mut depth = self.phase1_bound(twist, flip, slice);
+        // A phase 1 solution at least as long as the best solution cannot lead to a shorter one
+        while self.best.as_ref().is_none_or(|best| depth < best.len()) {
+            if self.phase1(twist, flip, slice, depth) {
+                break
+            }
+            depth += 1;
+        }
+    }
+
+    fn phase1_bound(&self, twist: usize, flip: usize, slice: usize) -> usize {
+        let tables = self.tables;
+        let twist = tables.twist_slice[twist * ESliceCombination::SIZE + slice];
+        let flip = tables.flip_slice[flip * ESliceCombination::SIZE + slice];
+        return twist.max(flip) as usize
+    }
+
+    fn phase2_bound(&self, corners: usize, edges: usize, slice: usize) -> usize {
+        let tables = self.tables;
+        let corners = tables.corner_slice[corners * ESlicePermutation::SIZE + slice];
+        let edges = tables.edge_slice[edges * ESlicePermutation::SIZE + slice];
+        return corners.max(edges) as usize
+    }
+
+    /// Searches all phase 1 solutions of exactly `depth` more moves. Returns true when done.
+    fn phase1(&mut self, twist: usize, flip: usize, slice: usize, depth: usize) -> bool {
+        if self.phase1_bound(twist, flip, slice) > depth {
+            return false
+        }
+        if depth == 0 {
+            // A phase 1 solution ending in a phase 2 move has a shorter one that was tried already
+            if self.moves.last().is_some_and(|turn| PHASE2_MOVES.contains(turn)) {
+                return false
+            }
+            return self.start_phase2()
+        }
+
+        let tables = self.tables;
+        for (i, turn) in MOVES.iter().enumerate() {
+            if !turn.may_follow(self.moves.last()) {
+                continue
+            }
+            let twist = tables.twist_moves.turn(twist, i);
+            let flip = tables.flip_moves.turn(flip, i);
+            let slice = tables.slice_moves.turn(slice, i);
+
+            self.moves.push(*turn);
+            let done = self.phase1(twist, flip, slice, depth - 1);
+            self.moves.pop();
+            if done {
+                return true
+            }
+        }
+        return false
+    }
+
+    /// Searches for the shortest phase 2 solution improving on the best solution so far.
+    fn start_phase2(&mut self) -> bool {
+        if self.best.is_some() && self.deadline.is_some_and(|deadline| Instant::now() > deadline) {
+            return true
+        }
+
+        let mut cube = self.cube;
+        cube.apply_moves(&self.moves);
+        let corners = CornerPermutation::coordinate(&cube);
+        let edges = UDEdgePermutation::coordinate(&cube);
+        let slice = ESlicePermutation::coordinate(&cube);
+
+        // Every state of the phase 2 subgroup can be solved in 18 moves
+        let phase1_length = self.moves.len();
+        let max_depth = match &self.best {
+            // Only solutions shorter than the best one are of interest
+            Some(best) => match best.len().checked_sub(phase1_length + 1) {
+                Some(max_depth) => max_depth.min(18),
+                None => return false,
+            },
+            None => 18,
+        };
+        for depth in self.phase2_bound(corners, edges, slice)..=max_depth {
+            if self.phase2(corners, edges, slice, depth) {
+                debug_assert!(self.best.as_ref().is_none_or(|best| self.moves.len() < best.len()));
+                self.best = Some(self.moves.clone());
+                self.moves.truncate(phase1_length);
+                return self.moves.len() + depth <= self.limits.target_length
+            }
+        }
+        return false
+    }
+
+    /**Searches for a phase 2 solution of exactly `depth` more moves. If one is found, it is
+     * left on the move stack and true is returned.
+     */
+    fn phase2(&mut self, corners: usize, edges: usize, slice: usize, depth: usize) -> bool {
+        if depth == 0 {
+            return corners == 0 && edges == 0 && slice == 0
+        }
+        if self.phase2_bound(corners, edges, slice) > depth {
+            return false
+        }
+
+        let tables = self.tables;
+        for (i, turn) in PHASE2_MOVES.iter().enumerate() {
+            if !turn.may_follow(self.moves.last()) {
+                continue
+            }
+            let corners = tables.corner_moves.turn(corners, i);
+            let edges = tables.edge_moves.turn(edges, i);
+            let slice = tables.slice_permutation_moves.turn(slice, i);
+
+            self.moves.push(*turn);
+            if self.phase2(corners, edges, slice, depth - 1) {
+                return true
+            }
+            self.moves.pop();
+        }
+        return false
+    }
+}
+
+/**Solve `cube` with the default limits. The tables are built on every call, so use a
+ * `KociembaSolver` directly when solving more than a single cube.
+ */
+pub fn solve(cube: &Cube) -> Result<Vec<Move>, SolveError> {
+    KociembaSolver::default().solve(cube)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::cube::{Axis, ExtendedMove};
+    use super::super::cube::Move::*;
+    use crate::test_fixtures::{flipped_edge, scrambled};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use std::sync::OnceLock;
+
+    /// Building the tables is slow without optimisations, so the tests share a solver.
+    fn solver() -> &'static KociembaSolver {
+        static SOLVER: OnceLock<KociembaSolver> = OnceLock::new();
+        SOLVER.get_or_init(KociembaSolver::default)
+    }
+
+    #[test]
+    fn solves_scrambles() {
+        assert!(solver().solve(&Cube::new()).unwrap().is_empty());
+
+        let mut cube = scrambled();
+        let solution = solver().solve(&cube).unwrap();
+        assert!(solution.len() <= 20);
+        assert!(cube.apply_moves(&solution).is_solved());
+
+        // Already in the phase 2 subgroup
+        let mut cube = Cube::new();
+        cube.apply_moves(&[U, R2, D, F2, L2, Up]);
+        let solution = solver().solve(&cube).unwrap();
+        assert_eq!(solution.len(), 6);
+        assert!(cube.apply_moves(&solution).is_solved());
+    }
+
+    #[test]
+    fn limits() {
+        let mut cube = Cube::new();
+        cube.apply_moves(&[R, U, Rp, Up, F2, D, Bp, L, F]);
+
+        // The first solution found is returned when it is short enough or there is no time left
+        for limits in [
+            SearchLimits { target_length: 30, time_budget: None },
+            SearchLimits { target_length: 0, time_budget: Some(Duration::ZERO) },
+        ] {
+            let solution = solver().solve_with_limits(&cube, &limits).unwrap();
+            let mut solved = cube;
+            assert!(solved.apply_moves(&solution).is_solved());
+        }
+
+        // Without a time budget the search continues until the target length is reached
+        let limits = SearchLimits { target_length: 9, time_budget: None };
+        assert!(solver().solve_with_limits(&cube, &limits).unwrap().len() <= 9);
+    }
+
+    #[test]
+    fn exhaustive_search_of_short_scrambles() {
+        // Phase 1 paths no shorter than the best solution used to underflow the phase 2 depth
+        let mut cube = Cube::new();
+        cube.apply_moves(&[Fp, R2, U2, R2, D2]);
+        let limits = SearchLimits { target_length: 0, time_budget: None };
+        assert_eq!(solver().solve_with_limits(&cube, &limits).unwrap().len(), 5);
+
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..50 {
+            let scramble: Vec<Move> = (0..6).map(|_| MOVES[rng.gen_range(0..MOVES.len())]).collect();
+            let mut cube = Cube::new();
+            cube.apply_moves(&scramble);
+            let solution = solver().solve_with_limits(&cube, &limits).unwrap();
+            assert!(solution.len() <= scramble.len());
+            assert!(cube.apply_moves(&solution).is_solved());
+        }
+    }
+
+    #[test]
+    fn solves_rotated_cube() {
+        let mut cube = Cube::new();
+        cube.turn_extended(&ExtendedMove::Rotation(Axis::X, 1)).turn_extended(&ExtendedMove::Rotation(Axis::Y, 1));
+        cube.apply_moves(&[R, U, Rp, Up, F2, D]);
+
+        let solution = solver().solve(&cube).unwrap();
+        assert!(cube.apply_moves(&solution).is_solved());
+    }
+
+    #[test]
+    fn rejects_invalid_cube() {
+        assert_eq!(solver().solve(&flipped_edge()), Err(SolveError::InvalidCube(CubeError::FlippedEdge)));
+    }
+}
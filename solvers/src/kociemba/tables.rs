@@ -0,0 +1,103 @@
+use super::coordinates::{
+    Coordinate, MoveTable, CornerOrientation, CornerPermutation, EdgeOrientation, ESliceCombination,
+    ESlicePermutation, UDEdgePermutation,
+};
+use super::cube::{Cube, Move, MOVES};
+
+/// The moves generating the phase 2 subgroup <U, D, R2, L2, F2, B2>.
+pub const PHASE2_MOVES: [Move; 10] = [
+    Move::U, Move::Up, Move::U2,
+    Move::D, Move::Dp, Move::D2,
+    Move::L2, Move::R2, Move::F2, Move::B2,
+];
+
+/**Distance to solved of each pair of coordinates `(a, b)` of `A` and `B`, stored at
+ * `a * B::SIZE + b`, using the move tables of both coordinates. The distances are lower bounds
+ * for the distance of the cube to the subgroup where both coordinates are solved.
+ */
+pub fn pruning_table<A: Coordinate, B: Coordinate>(moves_a: &MoveTable, moves_b: &MoveTable, n_moves: usize) -> Vec<u8> {
+    let size_b = B::SIZE;
+    let solved = Cube::new();
+    let mut table = vec![u8::MAX; A::SIZE * size_b];
+    table[A::coordinate(&solved) * size_b + B::coordinate(&solved)] = 0;
+
+    let mut depth = 0;
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for index in 0..table.len() {
+            if table[index] != depth {
+                continue
+            }
+            let (a, b) = (index / size_b, index % size_b);
+            for i in 0..n_moves {
+                let next = moves_a.turn(a, i) * size_b + moves_b.turn(b, i);
+                if table[next] == u8::MAX {
+                    table[next] = depth + 1;
+                    changed = true;
+                }
+            }
+        }
+        depth += 1;
+    }
+    return table
+}
+
+/// The move and pruning tables of both phases.
+pub struct Tables {
+    pub twist_moves: MoveTable,
+    pub flip_moves: MoveTable,
+    pub slice_moves: MoveTable,
+    pub corner_moves: MoveTable,
+    pub edge_moves: MoveTable,
+    pub slice_permutation_moves: MoveTable,
+    pub twist_slice: Vec<u8>,
+    pub flip_slice: Vec<u8>,
+    pub corner_slice: Vec<u8>,
+    pub edge_slice: Vec<u8>,
+}
+
+impl Tables {
+    pub fn new() -> Self {
+        let twist_moves = MoveTable::new::<CornerOrientation>(&MOVES);
+        let flip_moves = MoveTable::new::<EdgeOrientation>(&MOVES);
+        let slice_moves = MoveTable::new::<ESliceCombination>(&MOVES);
+        let corner_moves = MoveTable::new::<CornerPermutation>(&PHASE2_MOVES);
+        let edge_moves = MoveTable::new::<UDEdgePermutation>(&PHASE2_MOVES);
+        let slice_permutation_moves = MoveTable::new::<ESlicePermutation>(&PHASE2_MOVES);
+
+        Self {
+            twist_slice: pruning_table::<CornerOrientation, ESliceCombination>(&twist_moves, &slice_moves, MOVES.len()),
+            flip_slice: pruning_table::<EdgeOrientation, ESliceCombination>(&flip_moves, &slice_moves, MOVES.len()),
+            corner_slice: pruning_table::<CornerPermutation, ESlicePermutation>(&corner_moves, &slice_permutation_moves, PHASE2_MOVES.len()),
+            edge_slice: pruning_table::<UDEdgePermutation, ESlicePermutation>(&edge_moves, &slice_permutation_moves, PHASE2_MOVES.len()),
+            twist_moves,
+            flip_moves,
+            slice_moves,
+            corner_moves,
+            edge_moves,
+            slice_permutation_moves,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pruning_tables() {
+        let tables = Tables::new();
+        let slice = ESliceCombination::SIZE;
+        // The E-slice edges are in the E-slice for the coordinate 69
+        assert_eq!(tables.twist_slice[69], 0);
+        assert_eq!(tables.flip_slice[69], 0);
+        assert_eq!(tables.corner_slice[0], 0);
+
+        let mut cube = Cube::new();
+        cube.apply_moves(&[Move::R, Move::U, Move::F]);
+        let index = CornerOrientation::coordinate(&cube) * slice + ESliceCombination::coordinate(&cube);
+        assert!(tables.twist_slice[index] <= 3);
+        assert!(tables.twist_slice.iter().all(|&depth| depth != u8::MAX));
+    }
+}
@@ -1,2 +1,17 @@
+// The crate ends functions with explicit `return` statements as a matter of style
+#![allow(clippy::needless_return)]
+
+pub mod batch;
+pub mod coordinates;
 pub mod cube;
+pub mod cycles;
+pub mod facelet;
+pub mod kociemba;
+pub mod optimal;
+pub mod render;
+pub mod scramble;
+pub mod sequence;
+pub mod svg;
+#[cfg(test)]
+mod test_fixtures;
 pub mod thistlethwaite;
\ No newline at end of file
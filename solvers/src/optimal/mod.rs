@@ -0,0 +1,9 @@
+use super::coordinates;
+use super::cube;
+use super::thistlethwaite;
+
+mod patterns;
+mod solver;
+
+pub use patterns::{CornerPattern, EdgePattern1, EdgePattern2, SymmetricCornerPattern};
+pub use solver::{OptimalSolution, OptimalSolver};
@@ -0,0 +1,227 @@
+use super::cube::{Cube, Corner, Edge, Move, Symmetry, CORNERS, EDGES, FACES, MOVES};
+use super::coordinates::{
+    Coordinate, CombinedMoveTable, ConjugationTable, CornerOrientation, CornerPermutation, MoveTable,
+    SymmetryReduction,
+};
+use super::thistlethwaite::{Encoding, Stage};
+use std::sync::OnceLock;
+
+// A pattern database is a lookup table of the distance to solved of each configuration of a
+// subset of the pieces. This is a lower bound for the distance of the whole cube to solved,
+// which is what makes IDA* find optimal solutions. Patterns are stages whose move pool is
+// every move, so their tables are built and stored like those of any other stage. No pattern
+// is more than 11 moves from solved, so the tables are stored as nibbles.
+
+/// The permutation and orientation of all 8 corners: 8! * 3^7 = 88179840 configurations.
+pub struct CornerPattern;
+impl Stage<'static> for CornerPattern {
+    const NAME: &'static str = "CornerPattern";
+    const FILE_NAME: &'static str = "corners_full.dat";
+    const MOVE_POOL: &'static [Move] = &MOVES;
+    const ENCODING: Encoding = Encoding::Nibbles;
+    const SIZE: usize = 88179840;
+
+    fn indexer(cube: &Cube) -> usize {
+        return CornerPermutation::coordinate(cube) * 2187 + CornerOrientation::coordinate(cube)
+    }
+
+    fn cube(index: usize) -> Cube {
+        let permutation = partial_permutation_unrank(index / 2187, 8, 8);
+        let mut twist = index % 2187;
+        let mut corners = [(Corner::URF, 0); 8];
+        for (pos, &corner) in permutation.iter().enumerate() {
+            corners[pos] = (CORNERS[corner], 0);
+        }
+        for corner in corners[..7].iter_mut() {
+            corner.1 = (twist % 3) as u8;
+            twist /= 3;
+        }
+        corners[7].1 = (3 - corners[..7].iter().map(|corner| corner.1).sum::<u8>() % 3) % 3;
+        return Cube::from_pieces(EDGES.map(|edge| (edge, 0)), corners, FACES)
+    }
+
+    fn turn_index(index: usize, turn: usize) -> usize {
+        static TABLE: OnceLock<CombinedMoveTable> = OnceLock::new();
+        TABLE.get_or_init(|| CombinedMoveTable::new(vec![
+            (2187, MoveTable::new::<CornerPermutation>(Self::MOVE_POOL)),
+            (1, MoveTable::new::<CornerOrientation>(Self::MOVE_POOL)),
+        ])).turn(index, turn)
+    }
+}
+
+/**`CornerPattern` reduced by the 16 symmetries preserving the UD axis: the corner permutation
+ * is replaced by its class of conjugates, of which there are 2768, and the orientation is
+ * conjugated along with it. This is 2768 * 3^7 = 6053616 entries, 14.6 times fewer.
+ */
+pub struct SymmetricCornerPattern;
+
+struct SymmetricCornerTables {
+    reduction: SymmetryReduction,
+    conjugations: ConjugationTable,
+    permutation_moves: MoveTable,
+    orientation_moves: MoveTable,
+}
+
+impl SymmetricCornerPattern {
+    fn tables() -> &'static SymmetricCornerTables {
+        static TABLES: OnceLock<SymmetricCornerTables> = OnceLock::new();
+        TABLES.get_or_init(|| {
+            let symmetries = Symmetry::preserving_ud_axis();
+            SymmetricCornerTables {
+                reduction: SymmetryReduction::new::<CornerPermutation>(&symmetries),
+                conjugations: ConjugationTable::new::<CornerOrientation>(&symmetries),
+                permutation_moves: MoveTable::new::<CornerPermutation>(&MOVES),
+                orientation_moves: MoveTable::new::<CornerOrientation>(&MOVES),
+            }
+        })
+    }
+
+    /// The index of the class of the permutation `permutation` with the orientation `orientation`.
+    fn reduce(tables: &SymmetricCornerTables, permutation: usize, orientation: usize) -> usize {
+        let (class, symmetry) = tables.reduction.reduce(permutation);
+        return class * 2187 + tables.conjugations.conjugate(orientation, symmetry)
+    }
+}
+
+impl Stage<'static> for SymmetricCornerPattern {
+    const NAME: &'static str = "SymmetricCorners";
+    const FILE_NAME: &'static str = "corners.dat";
+    const MOVE_POOL: &'static [Move] = &MOVES;
+    const ENCODING: Encoding = Encoding::Nibbles;
+    const SIZE: usize = 6053616;
+
+    fn indexer(cube: &Cube) -> usize {
+        Self::reduce(Self::tables(), CornerPermutation::coordinate(cube), CornerOrientation::coordinate(cube))
+    }
+
+    fn cube(index: usize) -> Cube {
+        let permutation = Self::tables().reduction.representative(index / 2187);
+        CornerPattern::cube(permutation * 2187 + index % 2187)
+    }
+
+    fn turn_index(index: usize, turn: usize) -> usize {
+        let tables = Self::tables();
+        let permutation = tables.reduction.representative(index / 2187);
+        let permutation = tables.permutation_moves.turn(permutation, turn);
+        let orientation = tables.orientation_moves.turn(index % 2187, turn);
+        return Self::reduce(tables, permutation, orientation)
+    }
+}
+
+/**The positions and orientations of the six edges UR, UB, UL, UF, RF and RB:
+ * 12! / 6! * 2^6 = 42577920 configurations.
+ */
+pub struct EdgePattern1;
+impl Stage<'static> for EdgePattern1 {
+    const NAME: &'static str = "EdgePattern1";
+    const FILE_NAME: &'static str = "edges1.dat";
+    const MOVE_POOL: &'static [Move] = &MOVES;
+    const ENCODING: Encoding = Encoding::Nibbles;
+    const SIZE: usize = 42577920;
+
+    fn indexer(cube: &Cube) -> usize {
+        edge_index(cube, &EDGES[..6])
+    }
+
+    fn cube(index: usize) -> Cube {
+        edge_cube(index, &EDGES[..6])
+    }
+}
+
+/**The positions and orientations of the six edges LB, LF, DR, DB, DL and DF:
+ * 12! / 6! * 2^6 = 42577920 configurations.
+ */
+pub struct EdgePattern2;
+impl Stage<'static> for EdgePattern2 {
+    const NAME: &'static str = "EdgePattern2";
+    const FILE_NAME: &'static str = "edges2.dat";
+    const MOVE_POOL: &'static [Move] = &MOVES;
+    const ENCODING: Encoding = Encoding::Nibbles;
+    const SIZE: usize = 42577920;
+
+    fn indexer(cube: &Cube) -> usize {
+        edge_index(cube, &EDGES[6..])
+    }
+
+    fn cube(index: usize) -> Cube {
+        edge_cube(index, &EDGES[6..])
+    }
+}
+
+fn edge_index(cube: &Cube, edges: &[Edge]) -> usize {
+    let positions: Vec<usize> = edges.iter()
+        .map(|edge| *cube.get_edge_position(edge) as usize)
+        .collect();
+    let flip = positions.iter()
+        .rev()
+        .fold(0, |index, &pos| index * 2 + cube.get_edge_orientation(&EDGES[pos]) as usize);
+    return partial_permutation_rank(&positions, 12) * (1 << edges.len()) + flip
+}
+
+/// A cube with `edges` placed according to `index`, and the remaining edges filled in in order.
+fn edge_cube(index: usize, edges: &[Edge]) -> Cube {
+    let positions = partial_permutation_unrank(index >> edges.len(), edges.len(), 12);
+    let mut flip = index;
+    let mut pieces: [Option<(Edge, u8)>; 12] = [None; 12];
+    for (&edge, &pos) in edges.iter().zip(&positions) {
+        pieces[pos] = Some((edge, (flip % 2) as u8));
+        flip /= 2;
+    }
+    let mut others = EDGES.iter().filter(|edge| !edges.contains(edge));
+    let pieces = pieces.map(|piece| piece.unwrap_or_else(|| (*others.next().unwrap(), 0)));
+    return Cube::from_pieces(pieces, CORNERS.map(|corner| (corner, 0)), FACES)
+}
+
+/**Rank of an arrangement of `values.len()` distinct numbers from `0..n`, as a number in the
+ * mixed base n, n - 1, ... whose digits are the number of smaller values not yet used.
+ */
+fn partial_permutation_rank(values: &[usize], n: usize) -> usize {
+    let mut used = 0_u32;
+    values.iter()
+        .enumerate()
+        .fold(0, |rank, (i, &x)| {
+            let smaller_unused = (0..x).filter(|y| used & (1 << y) == 0).count();
+            used |= 1 << x;
+            rank * (n - i) + smaller_unused
+        })
+}
+
+/// Inverse of `partial_permutation_rank` for arrangements of `k` numbers.
+fn partial_permutation_unrank(mut rank: usize, k: usize, n: usize) -> Vec<usize> {
+    let mut digits = vec![0; k];
+    for i in (0..k).rev() {
+        digits[i] = rank % (n - i);
+        rank /= n - i;
+    }
+    let mut unused: Vec<usize> = (0..n).collect();
+    digits.iter().map(|&digit| unused.remove(digit)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn roundtrip<P: Stage<'static>>(rng: &mut StdRng) {
+        for _ in 0..1000 {
+            let index = rng.gen_range(0..P::SIZE);
+            assert_eq!(P::indexer(&P::cube(index)), index);
+            let turn = rng.gen_range(0..P::MOVE_POOL.len());
+            let mut cube = P::cube(index);
+            cube.turn(&P::MOVE_POOL[turn]);
+            assert_eq!(P::turn_index(index, turn), P::indexer(&cube));
+        }
+    }
+
+    #[test]
+    fn indices_roundtrip() {
+        let mut rng = StdRng::seed_from_u64(13);
+        roundtrip::<CornerPattern>(&mut rng);
+        roundtrip::<SymmetricCornerPattern>(&mut rng);
+        roundtrip::<EdgePattern1>(&mut rng);
+        roundtrip::<EdgePattern2>(&mut rng);
+
+        assert_eq!(partial_permutation_rank(&[11, 10, 9, 8, 7, 6], 12), 665279);
+        assert_eq!(partial_permutation_unrank(665279, 6, 12), [11, 10, 9, 8, 7, 6]);
+    }
+}
@@ -0,0 +1,160 @@
+use super::cube::{Cube, CubeError, Move, MOVES};
+use super::patterns::{EdgePattern1, EdgePattern2, SymmetricCornerPattern};
+use super::thistlethwaite::{BuildPolicy, LookupTable, TableError, TableStore};
+
+/// An optimal solution along with statistics of the search proving it optimal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OptimalSolution {
+    pub moves: Vec<Move>,
+    /**The proven minimal number of face turns (half-turn metric) solving the cube. Every
+     * shorter sequence has been ruled out by the search.
+     */
+    pub length: usize,
+    /// Number of nodes visited by the search.
+    pub nodes: u64,
+}
+
+/**Optimal solver following Korf: IDA* searching all sequences of increasing length, pruned
+ * by pattern databases of the corners and of each half of the edges. The largest distance
+ * given by the databases is a lower bound for the distance to solved. The corner database is
+ * reduced by symmetry.
+ *
+ * The databases take up around 45 MB and building them takes minutes, so they are saved
+ * to disk once built. Solving random cubes can still take a long time, since most need 17
+ * or 18 moves.
+ */
+pub struct OptimalSolver {
+    corners: LookupTable<SymmetricCornerPattern>,
+    edges1: LookupTable<EdgePattern1>,
+    edges2: LookupTable<EdgePattern2>,
+}
+
+impl OptimalSolver {
+    /**Loads the tables `corners.dat`, `edges1.dat` and `edges2.dat` from `store`. Tables
+     * that are missing, corrupt or outdated are built from scratch and saved in `store`.
+     */
+    pub fn new(store: &TableStore) -> Result<Self, TableError> {
+        Self::with_policy(store, BuildPolicy::IfInvalid)
+    }
+
+    /// Like `new`, but building tables as allowed by `policy`.
+    pub fn with_policy(store: &TableStore, policy: BuildPolicy) -> Result<Self, TableError> {
+        Ok(Self {
+            corners: store.load_or_build(policy)?,
+            edges1: store.load_or_build(policy)?,
+            edges2: store.load_or_build(policy)?,
+        })
+    }
+
+    /**Maps the tables from `store` into memory instead of reading them, see
+     * `LookupTable::map`. This returns at once, and processes mapping the same files share
+     * the memory holding them. Nothing is built, so the tables must have been saved before.
+     */
+    #[cfg(feature = "mmap")]
+    pub fn map(store: &TableStore) -> Result<Self, TableError> {
+        Ok(Self {
+            corners: store.map()?,
+            edges1: store.map()?,
+            edges2: store.map()?,
+        })
+    }
+
+    /// A lower bound for the number of moves needed to solve `cube`.
+    pub fn lower_bound(&self, cube: &Cube) -> u8 {
+        self.corners.eval(cube)
+            .max(self.edges1.eval(cube))
+            .max(self.edges2.eval(cube))
+    }
+
+    /**Finds a shortest sequence of face turns solving `cube`. If the cube has been rotated as
+     * a whole, the turns refer to the faces in their current position.
+     */
+    pub fn solve(&self, cube: &Cube) -> Result<OptimalSolution, CubeError> {
+        cube.validate()?;
+
+        let mut solution = ida_star(&cube.reoriented(), &|cube| self.lower_bound(cube));
+        solution.moves = cube.turns_from_reoriented(&solution.moves);
+        Ok(solution)
+    }
+}
+
+/**Iterative deepening A*: depth-first searches of increasing depth, cutting off branches as
+ * soon as the `lower_bound` of the distance to solved exceeds the remaining depth. The first
+ * solution found is optimal as long as `lower_bound` never overestimates.
+ */
+fn ida_star(cube: &Cube, lower_bound: &dyn Fn(&Cube) -> u8) -> OptimalSolution {
+    let mut moves = Vec::new();
+    let mut nodes = 0;
+    let mut depth = lower_bound(cube) as usize;
+    while !search(cube, depth, lower_bound, &mut moves, &mut nodes) {
+        depth += 1;
+    }
+    return OptimalSolution { moves, length: depth, nodes }
+}
+
+/// Searches for a solution of exactly `depth` more moves, leaving it in `moves` if found.
+fn search(cube: &Cube, depth: usize, lower_bound: &dyn Fn(&Cube) -> u8, moves: &mut Vec<Move>, nodes: &mut u64) -> bool {
+    *nodes += 1;
+    if depth == 0 {
+        return cube.is_solved()
+    }
+    if lower_bound(cube) as usize > depth {
+        return false
+    }
+
+    for turn in MOVES.iter() {
+        if !turn.may_follow(moves.last()) {
+            continue
+        }
+        let mut next = *cube;
+        next.turn(turn);
+        moves.push(*turn);
+        if search(&next, depth - 1, lower_bound, moves, nodes) {
+            return true
+        }
+        moves.pop();
+    }
+    return false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::cube::Move::*;
+
+    #[test]
+    fn finds_shortest_solutions() {
+        // Without pattern databases the search is a plain iterative deepening search
+        let lower_bound = |_: &Cube| 0;
+
+        let mut cube = Cube::new();
+        cube.apply_moves(&[R, U2, Fp, L]);
+        let solution = ida_star(&cube, &lower_bound);
+        assert_eq!(solution.length, 4);
+        assert_eq!(solution.moves, [Lp, F, U2, Rp]);
+
+        // Moves that cancel or commute are not counted
+        let mut cube = Cube::new();
+        cube.apply_moves(&[R, L, U, Up, Rp, D2]);
+        let solution = ida_star(&cube, &lower_bound);
+        assert_eq!(solution.length, 2);
+        assert!(cube.apply_moves(&solution.moves).is_solved());
+
+        assert_eq!(ida_star(&Cube::new(), &lower_bound).length, 0);
+    }
+
+    #[test]
+    #[ignore = "builds 45 MB of pattern databases, run with --release"]
+    fn solves_optimally() {
+        let store = TableStore::new(std::env::temp_dir().join("rubiks_cube_optimal"));
+        let solver = OptimalSolver::new(&store).unwrap();
+
+        let scramble = [R, U2, Fp, L, D, B2, Rp, U, F2, L2, D];
+        let mut cube = Cube::new();
+        cube.apply_moves(&scramble);
+        let solution = solver.solve(&cube).unwrap();
+        assert!(solution.length <= scramble.len());
+        assert!(solver.lower_bound(&cube) as usize <= solution.length);
+        assert!(cube.apply_moves(&solution.moves).is_solved());
+    }
+}
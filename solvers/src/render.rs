@@ -0,0 +1,112 @@
+use super::cube::{Cube, Face};
+use super::facelet::FaceletCube;
+use std::fmt;
+
+/// How stickers are drawn in the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// Coloured blocks using ANSI 256-colour escape codes.
+    Ansi,
+    /// The first letter of each colour: White, Red, Green, Yellow, Orange and Blue.
+    Ascii,
+}
+
+/// The colour of each face in the standard colour scheme, as an ANSI 256-colour code and a letter.
+fn colour(face: Face) -> (u8, char) {
+    match face {
+        Face::U => (15, 'W'),
+        Face::R => (196, 'R'),
+        Face::F => (40, 'G'),
+        Face::D => (226, 'Y'),
+        Face::L => (208, 'O'),
+        Face::B => (21, 'B'),
+    }
+}
+
+fn sticker(face: Face, style: Style) -> String {
+    let (code, letter) = colour(face);
+    match style {
+        Style::Ansi => format!("\x1b[48;5;{}m  \x1b[0m", code),
+        Style::Ascii => format!("{} ", letter),
+    }
+}
+
+/**Draws the cube as the standard cross-shaped net: U on top, then L, F, R and B from left
+ * to right, and D at the bottom.
+ *
+ * ```text
+ *       W W W
+ *       W W W
+ *       W W W
+ * O O O G G G R R R B B B
+ * O O O G G G R R R B B B
+ * O O O G G G R R R B B B
+ *       Y Y Y
+ *       Y Y Y
+ *       Y Y Y
+ * ```
+ */
+pub fn net(cube: &Cube, style: Style) -> String {
+    let facelets = FaceletCube::from(cube);
+    let row = |offset: usize, row: usize| -> String {
+        (0..3).map(|col| sticker(facelets.get(offset + row * 3 + col), style)).collect()
+    };
+    let padding = " ".repeat(6);
+    // Offsets of each face in the facelet string, see `FaceletCube`
+    let (u, r, f, d, l, b) = (0, 9, 18, 27, 36, 45);
+
+    let mut lines = Vec::new();
+    for i in 0..3 {
+        lines.push(format!("{}{}", padding, row(u, i)));
+    }
+    for i in 0..3 {
+        lines.push(format!("{}{}{}{}", row(l, i), row(f, i), row(r, i), row(b, i)));
+    }
+    for i in 0..3 {
+        lines.push(format!("{}{}", padding, row(d, i)));
+    }
+    lines.iter()
+        .map(|line| line.trim_end())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+impl fmt::Display for Cube {
+    /// Draws the net of the cube (see `net`), with colours if the alternate flag `{:#}` is used.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let style = if f.alternate() { Style::Ansi } else { Style::Ascii };
+        write!(f, "{}", net(self, style))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::cube::Move;
+
+    #[test]
+    fn ascii_net() {
+        let mut cube = Cube::new();
+        cube.turn(&Move::R);
+        let expected = [
+            "      W W G",
+            "      W W G",
+            "      W W G",
+            "O O O G G Y R R R W B B",
+            "O O O G G Y R R R W B B",
+            "O O O G G Y R R R W B B",
+            "      Y Y B",
+            "      Y Y B",
+            "      Y Y B",
+        ].join("\n");
+        assert_eq!(cube.to_string(), expected);
+    }
+
+    #[test]
+    fn ansi_net() {
+        let net = format!("{:#}", Cube::new());
+        assert_eq!(net.lines().count(), 9);
+        assert_eq!(net.matches("\x1b[48;5;15m").count(), 9);
+        assert_eq!(net.matches("\x1b[0m").count(), 54);
+    }
+}
@@ -0,0 +1,76 @@
+use super::coordinates::permutation_parity;
+use super::cube::{Cube, Edge, Corner, EDGES, CORNERS, FACES};
+use super::sequence::MoveSequence;
+use super::thistlethwaite::{SolveError, ThistlethwaiteSolver};
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+/**Samples a cube uniformly among all cubes reachable by turning a solved cube. The pieces 
+ * are shuffled at random, with two edges swapped if the edge and corner permutations have
+ * different parity. Every orientation but the last edge and corner is random, while the 
+ * last ones are chosen such that the total flip and twist are zero.
+ */
+pub fn random_cube<R: Rng + ?Sized>(rng: &mut R) -> Cube {
+    let mut edges: [Edge; 12] = EDGES;
+    let mut corners: [Corner; 8] = CORNERS;
+    edges.shuffle(rng);
+    corners.shuffle(rng);
+    if permutation_parity(&edges, &EDGES) != permutation_parity(&corners, &CORNERS) {
+        edges.swap(0, 1);
+    }
+
+    let mut edge_orientations = [0; 12];
+    for orientation in edge_orientations[..11].iter_mut() {
+        *orientation = rng.gen_range(0..2);
+    }
+    edge_orientations[11] = edge_orientations[..11].iter().sum::<u8>() % 2;
+
+    let mut corner_orientations = [0; 8];
+    for orientation in corner_orientations[..7].iter_mut() {
+        *orientation = rng.gen_range(0..3);
+    }
+    corner_orientations[7] = (3 - corner_orientations[..7].iter().sum::<u8>() % 3) % 3;
+
+    let edges = std::array::from_fn(|i| (edges[i], edge_orientations[i]));
+    let corners = std::array::from_fn(|i| (corners[i], corner_orientations[i]));
+    Cube::from_pieces(edges, corners, FACES)
+}
+
+/**A random-state scramble: the inverse of a solution to a uniformly random cube. Use a 
+ * seeded `rng` (e.g. `rand::rngs::StdRng::seed_from_u64`) for reproducible scrambles.
+ */
+pub fn random_scramble<R: Rng + ?Sized>(rng: &mut R, solver: &ThistlethwaiteSolver) -> Result<MoveSequence, SolveError> {
+    let cube = random_cube(rng);
+    let solution = MoveSequence::from(solver.solve(&cube)?);
+    Ok(solution.inverse())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::test_fixtures::shipped_solver;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn random_cubes_are_valid() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            assert_eq!(random_cube(&mut rng).validate(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn reproducible_scrambles() {
+        let solver = shipped_solver();
+        let scramble1 = random_scramble(&mut StdRng::seed_from_u64(42), &solver).unwrap();
+        let scramble2 = random_scramble(&mut StdRng::seed_from_u64(42), &solver).unwrap();
+        assert_eq!(scramble1, scramble2);
+
+        let mut cube = random_cube(&mut StdRng::seed_from_u64(42));
+        let mut scrambled = Cube::new();
+        scrambled.apply_moves(&scramble1);
+        assert_eq!(scrambled, cube);
+        assert!(cube.apply_moves(&solver.solve(&scrambled).unwrap()).is_solved());
+    }
+}
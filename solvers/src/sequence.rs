@@ -0,0 +1,604 @@
+use super::cube::{Axis, Cube, ExtendedMove, Face, Move, Slice};
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+
+/**A sequence of moves written in standard (WCA) notation, e.g. `R' U2 R2 D' L'`.
+ * Whitespace between moves is optional and may be repeated, and moves may be grouped
+ * by (balanced) parentheses or brackets, which are ignored when parsing.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct MoveSequence {
+    moves: Vec<Move>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// Expected a move but found nothing.
+    Empty,
+    /// The character does not denote a face.
+    UnknownFace(char),
+    /// The suffix following a face is not one of ``, `'`, `2`, `2'`, `3` or `3'`.
+    InvalidSuffix(String),
+    /// A closing bracket without matching opening bracket.
+    UnmatchedClose(char),
+    /// An opening bracket which is never closed.
+    UnclosedOpen(char),
+}
+
+/// Error from parsing moves. `position` is the index of the offending character (not byte).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub position: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ParseErrorKind::Empty => write!(f, "expected a move")?,
+            ParseErrorKind::UnknownFace(c) => write!(f, "unknown face `{}`", c)?,
+            ParseErrorKind::InvalidSuffix(s) => write!(f, "invalid move suffix `{}`", s)?,
+            ParseErrorKind::UnmatchedClose(c) => write!(f, "unmatched `{}`", c)?,
+            ParseErrorKind::UnclosedOpen(c) => write!(f, "unclosed `{}`", c)?,
+        }
+        write!(f, " at position {}", self.position)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl MoveSequence {
+    pub fn new() -> Self {
+        Self { moves: Vec::new() }
+    }
+
+    pub fn push(&mut self, turn: Move) {
+        self.moves.push(turn);
+    }
+
+    pub fn into_vec(self) -> Vec<Move> {
+        self.moves
+    }
+
+    /// The sequence undoing this sequence.
+    pub fn inverse(&self) -> MoveSequence {
+        self.moves.iter().rev().map(Move::inverse).collect()
+    }
+
+    /**The sequence mirrored in the plane perpendicular to `axis`. The faces on the axis are
+     * swapped, and every turn changes direction.
+     */
+    pub fn mirror(&self, axis: Axis) -> MoveSequence {
+        self.moves.iter()
+            .map(|turn| {
+                let face = turn.face();
+                let face = if face == axis.face() || face == axis.face().opposite() { face.opposite() } else { face };
+                Move::from_face_and_amount(face, 4 - turn.amount()).unwrap()
+            })
+            .collect()
+    }
+
+    /**Merges consecutive turns of the same face, also when separated by turns of the 
+     * opposite face (which commute with them), and removes turns cancelling out. E.g.
+     * `U D U2 F F'` simplifies to `U' D`.
+     */
+    pub fn simplify(&self) -> MoveSequence {
+        let mut result: Vec<Move> = Vec::with_capacity(self.moves.len());
+        for turn in &self.moves {
+            let face = turn.face();
+            let n = result.len();
+            let merge_with = if n >= 1 && result[n - 1].face() == face {
+                Some(n - 1)
+            } else if n >= 2 && result[n - 1].face() == face.opposite() && result[n - 2].face() == face {
+                Some(n - 2)
+            } else {
+                None
+            };
+
+            match merge_with {
+                None => result.push(*turn),
+                Some(i) => match Move::from_face_and_amount(face, result[i].amount() + turn.amount()) {
+                    Some(merged) => result[i] = merged,
+                    None => {
+                        result.remove(i);
+                    },
+                },
+            }
+        }
+        Self { moves: result }
+    }
+}
+
+impl Deref for MoveSequence {
+    type Target = [Move];
+
+    fn deref(&self) -> &[Move] {
+        &self.moves
+    }
+}
+
+impl From<Vec<Move>> for MoveSequence {
+    fn from(moves: Vec<Move>) -> Self {
+        Self { moves }
+    }
+}
+
+impl From<&[Move]> for MoveSequence {
+    fn from(moves: &[Move]) -> Self {
+        Self { moves: moves.to_vec() }
+    }
+}
+
+impl From<MoveSequence> for Vec<Move> {
+    fn from(sequence: MoveSequence) -> Self {
+        sequence.moves
+    }
+}
+
+impl FromIterator<Move> for MoveSequence {
+    fn from_iter<I: IntoIterator<Item = Move>>(iter: I) -> Self {
+        Self { moves: iter.into_iter().collect() }
+    }
+}
+
+impl IntoIterator for MoveSequence {
+    type Item = Move;
+    type IntoIter = std::vec::IntoIter<Move>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.moves.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a MoveSequence {
+    type Item = &'a Move;
+    type IntoIter = std::slice::Iter<'a, Move>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.moves.iter()
+    }
+}
+
+impl fmt::Display for MoveSequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, turn) in self.moves.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", turn)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for MoveSequence {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self { moves: parse_sequence(s, parse_move)? })
+    }
+}
+
+/**A sequence of moves which may include slice, wide and rotation moves, e.g. a 
+ * reconstruction like `x' R U' M' U2 r' y`. Wide moves are written as `Rw` and 
+ * parsed from either `Rw` or `r`.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ExtendedMoveSequence {
+    moves: Vec<ExtendedMove>,
+}
+
+impl ExtendedMoveSequence {
+    /**Rewrites the sequence using face turns only. Applying the result to a cube has the 
+     * same effect as applying the sequence and then rotating the cube back such that the 
+     * centers are in their home position (see `Cube::reoriented`).
+     */
+    pub fn to_face_turns(&self) -> MoveSequence {
+        // The centers of `frame` track how the cube has been rotated so far
+        let mut frame = Cube::new();
+        let mut result = MoveSequence::new();
+        let mut push = |frame: &Cube, face: Face, amount: u8| {
+            let face = *frame.get_center(&face);
+            if let Some(turn) = Move::from_face_and_amount(face, amount) {
+                result.push(turn);
+            }
+        };
+
+        for turn in &self.moves {
+            let rotation = match *turn {
+                ExtendedMove::Face(turn) => {
+                    push(&frame, turn.face(), turn.amount());
+                    continue
+                },
+                ExtendedMove::Slice(slice, amount) => {
+                    // The slice turns along with its face, so the outer layers are turned back
+                    let face = slice.face();
+                    push(&frame, face.opposite(), amount);
+                    push(&frame, face, 4 - amount % 4);
+                    (face, amount)
+                },
+                ExtendedMove::Wide(face, amount) => {
+                    push(&frame, face.opposite(), amount);
+                    (face, amount)
+                },
+                ExtendedMove::Rotation(axis, amount) => (axis.face(), amount),
+            };
+            frame.turn_extended(&rotation_around(rotation.0, rotation.1));
+        }
+        result
+    }
+
+    pub fn into_vec(self) -> Vec<ExtendedMove> {
+        self.moves
+    }
+}
+
+/// The whole cube rotation turning in the same direction as `face`.
+fn rotation_around(face: Face, amount: u8) -> ExtendedMove {
+    match face {
+        Face::R => ExtendedMove::Rotation(Axis::X, amount % 4),
+        Face::L => ExtendedMove::Rotation(Axis::X, (4 - amount % 4) % 4),
+        Face::U => ExtendedMove::Rotation(Axis::Y, amount % 4),
+        Face::D => ExtendedMove::Rotation(Axis::Y, (4 - amount % 4) % 4),
+        Face::F => ExtendedMove::Rotation(Axis::Z, amount % 4),
+        Face::B => ExtendedMove::Rotation(Axis::Z, (4 - amount % 4) % 4),
+    }
+}
+
+impl Deref for ExtendedMoveSequence {
+    type Target = [ExtendedMove];
+
+    fn deref(&self) -> &[ExtendedMove] {
+        &self.moves
+    }
+}
+
+impl From<Vec<ExtendedMove>> for ExtendedMoveSequence {
+    fn from(moves: Vec<ExtendedMove>) -> Self {
+        Self { moves }
+    }
+}
+
+impl From<MoveSequence> for ExtendedMoveSequence {
+    fn from(sequence: MoveSequence) -> Self {
+        sequence.into_iter().map(ExtendedMove::Face).collect()
+    }
+}
+
+impl FromIterator<ExtendedMove> for ExtendedMoveSequence {
+    fn from_iter<I: IntoIterator<Item = ExtendedMove>>(iter: I) -> Self {
+        Self { moves: iter.into_iter().collect() }
+    }
+}
+
+impl fmt::Display for ExtendedMoveSequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, turn) in self.moves.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", turn)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ExtendedMoveSequence {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self { moves: parse_sequence(s, parse_extended_move)? })
+    }
+}
+
+/// Parses the move at the start of a slice of characters, see `parse_move`.
+type TokenParser<M> = fn(&[char], usize) -> Result<(M, usize), ParseError>;
+
+/// Splits `s` into moves parsed by `parse_token`, skipping whitespace and brackets.
+fn parse_sequence<M>(s: &str, parse_token: TokenParser<M>) -> Result<Vec<M>, ParseError> {
+    let chars: Vec<char> = s.chars().collect();
+    let mut moves = Vec::new();
+    let mut brackets: Vec<(usize, char)> = Vec::new();
+
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '(' | '[' => {
+                brackets.push((i, c));
+                i += 1;
+            },
+            ')' | ']' => {
+                let open = if c == ')' { '(' } else { '[' };
+                match brackets.pop() {
+                    Some((_, bracket)) if bracket == open => i += 1,
+                    _ => return Err(ParseError { position: i, kind: ParseErrorKind::UnmatchedClose(c) }),
+                }
+            },
+            _ => {
+                let (turn, length) = parse_token(&chars[i..], i)?;
+                moves.push(turn);
+                i += length;
+            },
+        }
+    }
+
+    if let Some((position, bracket)) = brackets.pop() {
+        return Err(ParseError { position, kind: ParseErrorKind::UnclosedOpen(bracket) })
+    }
+    Ok(moves)
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", face_char(self.face()), suffix(self.amount()))
+    }
+}
+
+impl fmt::Display for ExtendedMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExtendedMove::Face(turn) => write!(f, "{}", turn),
+            ExtendedMove::Slice(slice, amount) => {
+                let slice = match slice {
+                    Slice::M => 'M',
+                    Slice::E => 'E',
+                    Slice::S => 'S',
+                };
+                write!(f, "{}{}", slice, suffix(*amount))
+            },
+            ExtendedMove::Wide(face, amount) => write!(f, "{}w{}", face_char(*face), suffix(*amount)),
+            ExtendedMove::Rotation(axis, amount) => {
+                let axis = match axis {
+                    Axis::X => 'x',
+                    Axis::Y => 'y',
+                    Axis::Z => 'z',
+                };
+                write!(f, "{}{}", axis, suffix(*amount))
+            },
+        }
+    }
+}
+
+fn face_char(face: Face) -> char {
+    match face {
+        Face::U => 'U',
+        Face::L => 'L',
+        Face::D => 'D',
+        Face::R => 'R',
+        Face::F => 'F',
+        Face::B => 'B',
+    }
+}
+
+fn suffix(amount: u8) -> &'static str {
+    match amount % 4 {
+        1 => "",
+        2 => "2",
+        3 => "'",
+        _ => "0",
+    }
+}
+
+impl FromStr for Move {
+    type Err = ParseError;
+
+    /// Parses a single move. Surrounding whitespace is not allowed.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let chars: Vec<char> = s.chars().collect();
+        if chars.is_empty() {
+            return Err(ParseError { position: 0, kind: ParseErrorKind::Empty })
+        }
+        let (turn, length) = parse_move(&chars, 0)?;
+        if length < chars.len() {
+            let suffix: String = chars[length..].iter().collect();
+            return Err(ParseError { position: length, kind: ParseErrorKind::InvalidSuffix(suffix) })
+        }
+        Ok(turn)
+    }
+}
+
+impl FromStr for ExtendedMove {
+    type Err = ParseError;
+
+    /// Parses a single move. Surrounding whitespace is not allowed.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let chars: Vec<char> = s.chars().collect();
+        if chars.is_empty() {
+            return Err(ParseError { position: 0, kind: ParseErrorKind::Empty })
+        }
+        let (turn, length) = parse_extended_move(&chars, 0)?;
+        if length < chars.len() {
+            let suffix: String = chars[length..].iter().collect();
+            return Err(ParseError { position: length, kind: ParseErrorKind::InvalidSuffix(suffix) })
+        }
+        Ok(turn)
+    }
+}
+
+/**Parses the move at the start of `chars`, returning it along with the number of characters
+ * it spans. `offset` is the position of `chars[0]` in the full input, used for errors.
+ */
+fn parse_move(chars: &[char], offset: usize) -> Result<(Move, usize), ParseError> {
+    let face = parse_face(chars[0])
+        .ok_or(ParseError { position: offset, kind: ParseErrorKind::UnknownFace(chars[0]) })?;
+    let (amount, length) = parse_suffix(&chars[1..], offset + 1)?;
+    Ok((Move::from_face_and_amount(face, amount).unwrap(), 1 + length))
+}
+
+/// Same as `parse_move`, but also accepting slice, wide and rotation moves.
+fn parse_extended_move(chars: &[char], offset: usize) -> Result<(ExtendedMove, usize), ParseError> {
+    let (turn, length): (fn(u8) -> ExtendedMove, usize) = match chars[0] {
+        'M' => (|amount| ExtendedMove::Slice(Slice::M, amount), 1),
+        'E' => (|amount| ExtendedMove::Slice(Slice::E, amount), 1),
+        'S' => (|amount| ExtendedMove::Slice(Slice::S, amount), 1),
+        'x' => (|amount| ExtendedMove::Rotation(Axis::X, amount), 1),
+        'y' => (|amount| ExtendedMove::Rotation(Axis::Y, amount), 1),
+        'z' => (|amount| ExtendedMove::Rotation(Axis::Z, amount), 1),
+        c => {
+            let face = parse_face(c.to_ascii_uppercase())
+                .ok_or(ParseError { position: offset, kind: ParseErrorKind::UnknownFace(c) })?;
+            let (amount, length) = if c.is_lowercase() || chars.get(1) == Some(&'w') {
+                let length = if c.is_lowercase() { 1 } else { 2 };
+                let (amount, suffix_length) = parse_suffix(&chars[length..], offset + length)?;
+                (amount, length + suffix_length)
+            } else {
+                let (turn, length) = parse_move(chars, offset)?;
+                return Ok((ExtendedMove::Face(turn), length))
+            };
+            return Ok((ExtendedMove::Wide(face, amount), length))
+        },
+    };
+    let (amount, suffix_length) = parse_suffix(&chars[length..], offset + length)?;
+    Ok((turn(amount), length + suffix_length))
+}
+
+fn parse_face(c: char) -> Option<Face> {
+    match c {
+        'U' => Some(Face::U),
+        'L' => Some(Face::L),
+        'D' => Some(Face::D),
+        'R' => Some(Face::R),
+        'F' => Some(Face::F),
+        'B' => Some(Face::B),
+        _ => None,
+    }
+}
+
+/**Parses the suffix at the start of `chars` into a number of clockwise quarter turns,
+ * returning it along with the number of characters it spans.
+ */
+fn parse_suffix(chars: &[char], offset: usize) -> Result<(u8, usize), ParseError> {
+    // The suffix is every character that can be part of a move modifier
+    let length = chars.iter()
+        .take_while(|c| c.is_ascii_digit() || is_prime(**c))
+        .count();
+    let suffix = &chars[..length];
+
+    let invalid_suffix = || ParseError {
+        position: offset,
+        kind: ParseErrorKind::InvalidSuffix(suffix.iter().collect()),
+    };
+    let (digit, prime) = match suffix {
+        [] => (None, false),
+        [p] if is_prime(*p) => (None, true),
+        [d] => (Some(*d), false),
+        [d, p] if is_prime(*p) => (Some(*d), true),
+        _ => return Err(invalid_suffix()),
+    };
+    let amount = match digit {
+        None | Some('1') => 1,
+        Some('2') => 2,
+        Some('3') => 3,
+        _ => return Err(invalid_suffix()),
+    };
+    let amount = if prime { 4 - amount } else { amount };
+
+    Ok((amount, length))
+}
+
+fn is_prime(c: char) -> bool {
+    c == '\'' || c == '’'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::cube::MOVES;
+    use super::super::cube::Move::*;
+
+    #[test]
+    fn display_and_parse_roundtrip() {
+        for turn in MOVES {
+            assert_eq!(turn.to_string().parse::<Move>(), Ok(turn));
+        }
+
+        let sequence: MoveSequence = "R' U2 R2 D' L'".parse().unwrap();
+        assert_eq!(&*sequence, &[Rp, U2, R2, Dp, Lp]);
+        assert_eq!(sequence.to_string(), "R' U2 R2 D' L'");
+    }
+
+    #[test]
+    fn tolerant_parsing() {
+        let sequence: MoveSequence = "  (R U R’ U')\t[F2  B2'] RU3\n".parse().unwrap();
+        assert_eq!(&*sequence, &[R, U, Rp, Up, F2, B2, R, Up]);
+        assert_eq!("".parse::<MoveSequence>().unwrap().len(), 0);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = "R U X".parse::<MoveSequence>().unwrap_err();
+        assert_eq!(error, ParseError { position: 4, kind: ParseErrorKind::UnknownFace('X') });
+
+        let error = "R U2'2".parse::<MoveSequence>().unwrap_err();
+        assert_eq!(error, ParseError { position: 3, kind: ParseErrorKind::InvalidSuffix("2'2".into()) });
+
+        let error = "(R U]".parse::<MoveSequence>().unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::UnmatchedClose(']'));
+
+        let error = "R [U".parse::<MoveSequence>().unwrap_err();
+        assert_eq!(error, ParseError { position: 2, kind: ParseErrorKind::UnclosedOpen('[') });
+
+        assert!("R ".parse::<Move>().is_err());
+        assert!("r".parse::<Move>().is_err());
+        assert_eq!("".parse::<Move>().unwrap_err().kind, ParseErrorKind::Empty);
+    }
+
+    #[test]
+    fn inverse_and_mirror() {
+        let sequence: MoveSequence = "R U R' U' F2 D".parse().unwrap();
+        assert_eq!(sequence.inverse().to_string(), "D' F2 U R U' R'");
+        assert_eq!(sequence.mirror(Axis::X).to_string(), "L' U' L U F2 D'");
+        assert_eq!(sequence.mirror(Axis::Y).to_string(), "R' D' R D F2 U'");
+        assert_eq!(sequence.mirror(Axis::Z).to_string(), "R' U' R U B2 D'");
+
+        let mut cube = Cube::new();
+        cube.apply_moves(&sequence).apply_moves(&sequence.inverse());
+        assert!(cube.is_solved());
+    }
+
+    #[test]
+    fn simplify() {
+        let simplify = |s: &str| s.parse::<MoveSequence>().unwrap().simplify().to_string();
+        assert_eq!(simplify("U D U2 F F'"), "U' D");
+        assert_eq!(simplify("R L R' L'"), "");
+        assert_eq!(simplify("R U D D' U' R'"), "");
+        assert_eq!(simplify("R U2 U2 L R2 L"), "R' L2");
+        assert_eq!(simplify("F B U D"), "F B U D");
+
+        let sequence: MoveSequence = "R U2 D' U2 D F F2 B L R L' R F' B2".parse().unwrap();
+        let mut cube1 = Cube::new();
+        cube1.apply_moves(&sequence);
+        let mut cube2 = Cube::new();
+        cube2.apply_moves(&sequence.simplify());
+        assert_eq!(cube1, cube2);
+        assert_eq!(sequence.simplify().len(), 6);
+    }
+
+    #[test]
+    fn extended_notation() {
+        let sequence: ExtendedMoveSequence = "x' R U' M' U2 r' y Fw2 E S' z2".parse().unwrap();
+        assert_eq!(sequence.len(), 11);
+        assert_eq!(sequence[3], ExtendedMove::Slice(Slice::M, 3));
+        assert_eq!(sequence[5], ExtendedMove::Wide(Face::R, 3));
+        assert_eq!(sequence.to_string(), "x' R U' M' U2 Rw' y Fw2 E S' z2");
+
+        let error = "R Xw".parse::<ExtendedMoveSequence>().unwrap_err();
+        assert_eq!(error, ParseError { position: 2, kind: ParseErrorKind::UnknownFace('X') });
+    }
+
+    #[test]
+    fn reduce_to_face_turns() {
+        let sequence: ExtendedMoveSequence = "x' R U' M' U2 r' y Fw2 E S' z2 u d' l2 b".parse().unwrap();
+        let mut cube1 = Cube::new();
+        cube1.apply_extended_moves(&sequence);
+        let mut cube2 = Cube::new();
+        cube2.apply_moves(&sequence.to_face_turns());
+        assert_eq!(cube1.reoriented(), cube2);
+
+        let sequence: ExtendedMoveSequence = "M2 U M U2 M' U M2".parse().unwrap();
+        assert_eq!(sequence.to_face_turns().to_string(), "R2 L2 D R L' F2 R' L D R2 L2");
+    }
+}
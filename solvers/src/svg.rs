@@ -0,0 +1,233 @@
+use super::cube::{Cube, Face, Move, CORNERS, EDGES};
+use super::cycles::Cycle;
+use super::facelet::FaceletCube;
+use super::sequence::MoveSequence;
+use std::fmt::Write;
+
+/// Fill colour (any SVG colour, e.g. `#ffffff` or `white`) of the stickers of each face.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColourScheme {
+    colours: [String; 6],
+}
+
+impl ColourScheme {
+    /// The standard (western) colour scheme with white on top and green in front.
+    pub fn standard() -> Self {
+        let mut colours: [String; 6] = Default::default();
+        colours[Face::U as usize] = "#ffffff".into();
+        colours[Face::L as usize] = "#ff8c00".into();
+        colours[Face::D as usize] = "#ffd500".into();
+        colours[Face::R as usize] = "#c41e3a".into();
+        colours[Face::F as usize] = "#009e60".into();
+        colours[Face::B as usize] = "#0051ba".into();
+        Self { colours }
+    }
+
+    /// The same scheme with the stickers of `face` coloured `colour`.
+    pub fn with(mut self, face: Face, colour: &str) -> Self {
+        self.colours[face as usize] = colour.into();
+        self
+    }
+
+    pub fn get(&self, face: Face) -> &str {
+        &self.colours[face as usize]
+    }
+}
+
+impl Default for ColourScheme {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum View {
+    /// The cross-shaped net with all six faces, as in the terminal rendering.
+    Net,
+    /// The U, F and R faces seen from above the URF corner.
+    Isometric,
+    /// The U face seen from above, with the side stickers of the U layer around it and arrows
+    /// showing how the U layer pieces are cycled.
+    LastLayer,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgOptions {
+    pub view: View,
+    pub colours: ColourScheme,
+    /// Side length of a sticker in pixels.
+    pub sticker_size: f64,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self { view: View::Net, colours: ColourScheme::standard(), sticker_size: 20.0 }
+    }
+}
+
+const STROKE: &str = r##"stroke="#000000" stroke-width="1""##;
+
+/// An SVG image of `cube`.
+pub fn to_svg(cube: &Cube, options: &SvgOptions) -> String {
+    let facelets = FaceletCube::from(cube);
+    let colour = |index: usize| options.colours.get(facelets.get(index));
+    let s = options.sticker_size;
+    let mut body = String::new();
+
+    let (width, height) = match options.view {
+        View::Net => {
+            // Position of each face in the net in units of faces, in facelet string order
+            let offsets = [(1, 0), (2, 1), (1, 1), (1, 2), (0, 1), (3, 1)];
+            for (face, (x, y)) in offsets.iter().enumerate() {
+                for i in 0..9 {
+                    let x = (3 * x + i % 3) as f64 * s;
+                    let y = (3 * y + i / 3) as f64 * s;
+                    writeln!(body, r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}" {}/>"#, x, y, s, s, colour(face * 9 + i), STROKE).unwrap();
+                }
+            }
+            (12.0 * s, 9.0 * s)
+        },
+        View::Isometric => {
+            // Project the point (x, y, z) of the cube [0, 3]^3, where the U face has y = 3, the
+            // F face z = 3 and the R face x = 3.
+            let cos30 = 3f64.sqrt() / 2.0;
+            let project = |(x, y, z): (f64, f64, f64)| ((x - z) * cos30 * s + 3.0 * cos30 * s, ((x + z) / 2.0 - y) * s + 3.0 * s);
+            let mut quad = |index: usize, corners: [(f64, f64, f64); 4]| {
+                let points: Vec<String> = corners.iter()
+                    .map(|&p| project(p))
+                    .map(|(x, y)| format!("{:.2},{:.2}", x, y))
+                    .collect();
+                writeln!(body, r#"<polygon points="{}" fill="{}" {}/>"#, points.join(" "), colour(index), STROKE).unwrap();
+            };
+            for i in 0..9 {
+                let (r, c) = ((i / 3) as f64, (i % 3) as f64);
+                quad(i, [(c, 3.0, r), (c + 1.0, 3.0, r), (c + 1.0, 3.0, r + 1.0), (c, 3.0, r + 1.0)]);
+                quad(9 + i, [(3.0, 3.0 - r, 3.0 - c), (3.0, 3.0 - r, 2.0 - c), (3.0, 2.0 - r, 2.0 - c), (3.0, 2.0 - r, 3.0 - c)]);
+                quad(18 + i, [(c, 3.0 - r, 3.0), (c + 1.0, 3.0 - r, 3.0), (c + 1.0, 2.0 - r, 3.0), (c, 2.0 - r, 3.0)]);
+            }
+            (6.0 * cos30 * s, 6.0 * s)
+        },
+        View::LastLayer => {
+            // The U face is surrounded by a thin row of stickers from each side face
+            let t = s / 3.0;
+            let rect = |body: &mut String, x: f64, y: f64, w: f64, h: f64, index: usize| {
+                writeln!(body, r#"<rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" fill="{}" {}/>"#, x, y, w, h, colour(index), STROKE).unwrap();
+            };
+            for i in 0..3 {
+                let k = i as f64;
+                rect(&mut body, t + k * s, t, s, s, i);
+                rect(&mut body, t + k * s, t + s, s, s, 3 + i);
+                rect(&mut body, t + k * s, t + 2.0 * s, s, s, 6 + i);
+                // B and R are read from the right when seen from above
+                rect(&mut body, t + (2.0 - k) * s, 0.0, s, t, 45 + i);
+                rect(&mut body, t + k * s, t + 3.0 * s, s, t, 18 + i);
+                rect(&mut body, 0.0, t + k * s, t, s, 36 + i);
+                rect(&mut body, t + 3.0 * s, t + (2.0 - k) * s, t, s, 9 + i);
+            }
+            write_arrows(&mut body, cube, s, t);
+            (3.0 * s + 2.0 * t, 3.0 * s + 2.0 * t)
+        },
+    };
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w:.2}\" height=\"{h:.2}\" viewBox=\"0 0 {w:.2} {h:.2}\">\n{}</svg>\n",
+        body, w = width, h = height,
+    )
+}
+
+/**An SVG image of the case solved by `moves`, i.e. a solved cube with the inverse of `moves`
+ * applied. This is how algorithm sheets show algorithms.
+ */
+pub fn algorithm_diagram(moves: &[Move], options: &SvgOptions) -> String {
+    let mut cube = Cube::new();
+    cube.apply_moves(&MoveSequence::from(moves).inverse());
+    to_svg(&cube, options)
+}
+
+/**Arrows on the U face of the last layer view for each cycle of pieces that stays within the
+ * U layer. Each arrow points from where a piece is to where it belongs, so that they show what
+ * an algorithm solving the state does.
+ */
+fn write_arrows(body: &mut String, cube: &Cube, s: f64, t: f64) {
+    // Center of each U layer position on the U face, in units of stickers
+    let corner_center = |pos: usize| [(2.5, 2.5), (2.5, 0.5), (0.5, 0.5), (0.5, 2.5)].get(pos).copied();
+    let edge_center = |pos: usize| [(2.5, 1.5), (1.5, 0.5), (0.5, 1.5), (1.5, 2.5)].get(pos).copied();
+
+    let cycles = cube.cycles();
+    let mut paths: Vec<Vec<(f64, f64)>> = Vec::new();
+    let mut add = |cycles: Vec<Vec<usize>>, center: &dyn Fn(usize) -> Option<(f64, f64)>| {
+        for cycle in cycles {
+            // The piece at `cycle[i + 1]` belongs at `cycle[i]`
+            if let Some(points) = cycle.iter().rev().map(|&pos| center(pos)).collect::<Option<Vec<_>>>() {
+                paths.push(points);
+            }
+        }
+    };
+    add(positions(&cycles.corners, &CORNERS), &corner_center);
+    add(positions(&cycles.edges, &EDGES), &edge_center);
+
+    let mut arrows = String::new();
+    for points in paths.iter().filter(|points| points.len() > 1) {
+        for (i, &(x1, y1)) in points.iter().enumerate() {
+            let (x2, y2) = points[(i + 1) % points.len()];
+            // Stop short of the centers, so arrows in a 2-cycle do not hide each other
+            let (dx, dy) = (x2 - x1, y2 - y1);
+            let length = (dx * dx + dy * dy).sqrt();
+            let shorten = 0.25 / length;
+            let (x1, y1) = (x1 + dx * shorten, y1 + dy * shorten);
+            let (x2, y2) = (x2 - dx * shorten, y2 - dy * shorten);
+            writeln!(
+                arrows,
+                r##"<line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}" stroke="#000000" stroke-width="2" marker-end="url(#arrow)"/>"##,
+                t + x1 * s, t + y1 * s, t + x2 * s, t + y2 * s,
+            ).unwrap();
+        }
+    }
+    if !arrows.is_empty() {
+        body.push_str(r##"<defs><marker id="arrow" viewBox="0 0 10 10" refX="9" refY="5" markerWidth="5" markerHeight="5" orient="auto"><path d="M0,0 L10,5 L0,10 z" fill="#000000"/></marker></defs>"##);
+        body.push('\n');
+        body.push_str(&arrows);
+    }
+}
+
+/// The cycles with pieces replaced by their index in `pieces`.
+fn positions<T: PartialEq>(cycles: &[Cycle<T>], pieces: &[T]) -> Vec<Vec<usize>> {
+    cycles.iter()
+        .map(|cycle| cycle.pieces.iter().map(|piece| pieces.iter().position(|p| p == piece).unwrap()).collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn views() {
+        let mut cube = Cube::new();
+        cube.apply_moves(&"R U R' U'".parse::<MoveSequence>().unwrap());
+
+        let net = to_svg(&cube, &SvgOptions::default());
+        assert!(net.starts_with("<svg"));
+        assert_eq!(net.matches("<rect").count(), 54);
+
+        let options = SvgOptions { view: View::Isometric, ..Default::default() };
+        assert_eq!(to_svg(&cube, &options).matches("<polygon").count(), 27);
+
+        let options = SvgOptions { colours: ColourScheme::standard().with(Face::U, "grey"), ..Default::default() };
+        assert_eq!(to_svg(&Cube::new(), &options).matches(r#"fill="grey""#).count(), 9);
+    }
+
+    #[test]
+    fn last_layer_arrows() {
+        let options = SvgOptions { view: View::LastLayer, ..Default::default() };
+        let solved = to_svg(&Cube::new(), &options);
+        assert_eq!(solved.matches("<rect").count(), 21);
+        assert_eq!(solved.matches("<line").count(), 0);
+
+        // The U perm cycles three edges, and the T perm swaps two corners and two edges
+        let u_perm = algorithm_diagram(&"R U' R U R U R U' R' U' R2".parse::<MoveSequence>().unwrap(), &options);
+        assert_eq!(u_perm.matches("<line").count(), 3);
+        let t_perm = algorithm_diagram(&"R U R' U' R' F R2 U' R' U' R U R' F'".parse::<MoveSequence>().unwrap(), &options);
+        assert_eq!(t_perm.matches("<line").count(), 4);
+    }
+}
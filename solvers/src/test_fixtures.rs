@@ -0,0 +1,27 @@
+use super::cube::{Cube, Move, EDGES, CORNERS, FACES};
+use super::cube::Move::*;
+use super::thistlethwaite::{BuildPolicy, TableStore, ThistlethwaiteSolver, DATA_DIR};
+
+/// A random-state scramble of 19 moves, used by the tests of several modules.
+pub const SCRAMBLE: [Move; 19] = [Rp, U2, R2, Dp, Lp, Bp, L2, Up, R2, D2, R, B2, Lp, D2, Rp, F2, B2, R, F];
+
+/// The cube scrambled by `SCRAMBLE`.
+pub fn scrambled() -> Cube {
+    let mut cube = Cube::new();
+    cube.apply_moves(&SCRAMBLE);
+    return cube
+}
+
+/// A solved cube with a single edge flipped, which no sequence of moves reaches.
+pub fn flipped_edge() -> Cube {
+    let mut edges = EDGES.map(|edge| (edge, 0));
+    edges[0].1 = 1;
+    return Cube::from_pieces(edges, CORNERS.map(|corner| (corner, 0)), FACES)
+}
+
+/**A solver reading the tables shipped with the crate, so tests neither build tables nor
+ * write to the user's table directory.
+ */
+pub fn shipped_solver() -> ThistlethwaiteSolver {
+    return ThistlethwaiteSolver::with_policy(&TableStore::new(DATA_DIR), BuildPolicy::Never).unwrap()
+}
@@ -0,0 +1,96 @@
+/**How the distances of a lookup table are stored, both in memory and on disk. Stages choose
+ * an encoding by `Stage::ENCODING`. Entries without a known distance are stored as the largest
+ * value of the encoding and read as `u8::MAX`.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// One distance per byte.
+    Bytes,
+    /// Two distances per byte, for tables where every distance is below 15.
+    Nibbles,
+    /**Four distances per byte, each stored mod 3. The distances of neighbouring entries differ
+     * by at most one, so the exact distance is recovered by descending to solved, always
+     * following a move to an entry whose distance is one less mod 3.
+     */
+    Mod3,
+}
+
+impl Encoding {
+    /// Number of bytes needed to store `size` entries.
+    pub fn encoded_len(&self, size: usize) -> usize {
+        match self {
+            Encoding::Bytes => size,
+            Encoding::Nibbles => size.div_ceil(2),
+            Encoding::Mod3 => size.div_ceil(4),
+        }
+    }
+
+    /**Encodes a table of distances, with `u8::MAX` for entries without a known distance. Fails
+     * with the first distance that the encoding cannot store.
+     */
+    pub(crate) fn encode(&self, distances: &[u8]) -> Result<Box<[u8]>, u8> {
+        // 15 marks unknown entries, so known distances must be below it
+        if let (Encoding::Nibbles, Some(&distance)) = (self, distances.iter().find(|&&d| d >= 15 && d != u8::MAX)) {
+            return Err(distance)
+        }
+        let data = match self {
+            Encoding::Bytes => distances.into(),
+            Encoding::Nibbles => distances.chunks(2)
+                .map(|pair| pair.iter().enumerate().fold(0, |byte, (i, &distance)| {
+                    let value = if distance == u8::MAX { 15 } else { distance };
+                    byte | value << (4 * i)
+                }))
+                .collect(),
+            Encoding::Mod3 => distances.chunks(4)
+                .map(|quad| quad.iter().enumerate().fold(0, |byte, (i, &distance)| {
+                    let value = if distance == u8::MAX { 3 } else { distance % 3 };
+                    byte | value << (2 * i)
+                }))
+                .collect(),
+        };
+        return Ok(data)
+    }
+
+    /**The value stored for entry `index` of the encoded table `data`: the distance, or the
+     * distance mod 3 for `Mod3`. `u8::MAX` if the distance is unknown.
+     */
+    pub(crate) fn get(&self, data: &[u8], index: usize) -> u8 {
+        let (value, unknown) = match self {
+            Encoding::Bytes => return data[index],
+            Encoding::Nibbles => (data[index / 2] >> (4 * (index % 2)) & 15, 15),
+            Encoding::Mod3 => (data[index / 4] >> (2 * (index % 4)) & 3, 3),
+        };
+        if value == unknown {
+            return u8::MAX
+        }
+        return value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let distances = [0, 1, 2, 3, 4, 14, u8::MAX, 7, 1];
+        for encoding in [Encoding::Bytes, Encoding::Nibbles, Encoding::Mod3] {
+            let data = encoding.encode(&distances).unwrap();
+            assert_eq!(data.len(), encoding.encoded_len(distances.len()));
+            for (index, &distance) in distances.iter().enumerate() {
+                let expected = match encoding {
+                    Encoding::Mod3 if distance != u8::MAX => distance % 3,
+                    _ => distance,
+                };
+                assert_eq!(encoding.get(&data, index), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_large_distances() {
+        // 15 is the nibble marking unknown entries
+        assert_eq!(Encoding::Nibbles.encode(&[0, 15, 16]), Err(15));
+        assert!(Encoding::Mod3.encode(&[0, 15, 16]).is_ok());
+    }
+}
@@ -0,0 +1,238 @@
+use super::cube::Cube;
+use super::encoding::Encoding;
+use super::stages::Stage;
+
+/// The first bytes of every table file.
+pub const MAGIC: [u8; 4] = *b"RCLT";
+/// Version of the file format, increased whenever the layout of the files changes.
+pub const FORMAT_VERSION: u16 = 1;
+/// Number of bytes in front of the encoded table.
+pub const HEADER_LEN: usize = 47;
+
+/**The header in front of the encoded table in a table file:
+ *
+ * | bytes | content                                          |
+ * |-------|--------------------------------------------------|
+ * | 0-3   | `MAGIC`                                          |
+ * | 4-5   | `FORMAT_VERSION`                                 |
+ * | 6-21  | `Stage::NAME`, padded with zeros                 |
+ * | 22    | the encoding: 0 bytes, 1 nibbles, 2 mod 3        |
+ * | 23-30 | number of entries, `Stage::SIZE`                 |
+ * | 31-38 | hash of the stage definition                     |
+ * | 39-46 | checksum of the encoded table                    |
+ *
+ * Numbers are little endian. Hashes and checksums are 64-bit FNV-1a.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Header {
+    version: u16,
+    stage: [u8; 16],
+    encoding: u8,
+    entries: u64,
+    definition: u64,
+    checksum: u64,
+}
+
+/// Ways a table file can fail to match the stage it is loaded for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormatError {
+    /// The file is not a table file, or is too short to hold the header.
+    NotATable,
+    /// The file was written in another version of the format.
+    Version { found: u16, expected: u16 },
+    /// The file holds the table of another stage.
+    Stage { found: String, expected: String },
+    /// The table is stored with another encoding than the stage uses.
+    Encoding,
+    /// The header or the length of the file gives another number of entries than the stage has.
+    Size { found: u64, expected: u64 },
+    /// The stage was defined differently when the table was built, e.g. by an older indexer.
+    Definition,
+    /// The table does not match its checksum.
+    Checksum,
+}
+
+impl std::fmt::Display for FormatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FormatError::NotATable => write!(f, "the file is not a lookup table"),
+            FormatError::Version { found, expected } => {
+                write!(f, "the table has format version {}, but version {} is supported", found, expected)
+            },
+            FormatError::Stage { found, expected } => {
+                write!(f, "the table is for stage {}, but stage {} was expected", found, expected)
+            },
+            FormatError::Encoding => write!(f, "the table is stored with another encoding than the stage uses"),
+            FormatError::Size { found, expected } => {
+                write!(f, "the table has {} entries, but the stage has {}", found, expected)
+            },
+            FormatError::Definition => write!(f, "the table was built for an older definition of the stage"),
+            FormatError::Checksum => write!(f, "the table does not match its checksum"),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+impl Header {
+    /// The header of the encoded table `data` of the stage `S`.
+    pub fn new<'a, S: Stage<'a>>(data: &[u8]) -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            stage: stage_name::<S>(),
+            encoding: encoding_id(S::ENCODING),
+            entries: S::SIZE as u64,
+            definition: definition_hash::<S>(),
+            checksum: fnv1a(FNV_OFFSET, data),
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; HEADER_LEN] {
+        let mut bytes = [0; HEADER_LEN];
+        bytes[0..4].copy_from_slice(&MAGIC);
+        bytes[4..6].copy_from_slice(&self.version.to_le_bytes());
+        bytes[6..22].copy_from_slice(&self.stage);
+        bytes[22] = self.encoding;
+        bytes[23..31].copy_from_slice(&self.entries.to_le_bytes());
+        bytes[31..39].copy_from_slice(&self.definition.to_le_bytes());
+        bytes[39..47].copy_from_slice(&self.checksum.to_le_bytes());
+        return bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, FormatError> {
+        if bytes.len() < HEADER_LEN || bytes[0..4] != MAGIC {
+            return Err(FormatError::NotATable)
+        }
+        let u64_at = |i: usize| u64::from_le_bytes(bytes[i..i + 8].try_into().unwrap());
+        Ok(Self {
+            version: u16::from_le_bytes([bytes[4], bytes[5]]),
+            stage: bytes[6..22].try_into().unwrap(),
+            encoding: bytes[22],
+            entries: u64_at(23),
+            definition: u64_at(31),
+            checksum: u64_at(39),
+        })
+    }
+
+    /**Checks that `file` is a complete table file of the stage `S`, and returns the encoded
+     * table following the header.
+     */
+    pub fn check<'a, 'f, S: Stage<'a>>(file: &'f [u8]) -> Result<&'f [u8], FormatError> {
+        let data = Self::check_layout::<S>(file)?;
+        if Self::from_bytes(file)?.checksum != fnv1a(FNV_OFFSET, data) {
+            return Err(FormatError::Checksum)
+        }
+        return Ok(data)
+    }
+
+    /**Like `check`, but without verifying the checksum, which takes reading the whole table.
+     * Only the header and the length of `file` are read.
+     */
+    pub fn check_layout<'a, 'f, S: Stage<'a>>(file: &'f [u8]) -> Result<&'f [u8], FormatError> {
+        let header = Self::from_bytes(file)?;
+        if header.version != FORMAT_VERSION {
+            return Err(FormatError::Version { found: header.version, expected: FORMAT_VERSION })
+        }
+        if header.stage != stage_name::<S>() {
+            let name = |stage: &[u8]| String::from_utf8_lossy(stage).trim_end_matches('\0').to_string();
+            return Err(FormatError::Stage { found: name(&header.stage), expected: name(&stage_name::<S>()) })
+        }
+        if header.encoding != encoding_id(S::ENCODING) {
+            return Err(FormatError::Encoding)
+        }
+        if header.entries != S::SIZE as u64 {
+            return Err(FormatError::Size { found: header.entries, expected: S::SIZE as u64 })
+        }
+        let data = &file[HEADER_LEN..];
+        if data.len() != S::ENCODING.encoded_len(S::SIZE) {
+            return Err(FormatError::Size { found: data.len() as u64, expected: S::ENCODING.encoded_len(S::SIZE) as u64 })
+        }
+        if header.definition != definition_hash::<S>() {
+            return Err(FormatError::Definition)
+        }
+        return Ok(data)
+    }
+}
+
+fn stage_name<'a, S: Stage<'a>>() -> [u8; 16] {
+    let mut name = [0; 16];
+    let bytes = S::NAME.as_bytes();
+    assert!(bytes.len() <= 16, "stage names are at most 16 bytes");
+    name[..bytes.len()].copy_from_slice(bytes);
+    return name
+}
+
+fn encoding_id(encoding: Encoding) -> u8 {
+    match encoding {
+        Encoding::Bytes => 0,
+        Encoding::Nibbles => 1,
+        Encoding::Mod3 => 2,
+    }
+}
+
+/**A hash of the behaviour of the stage: its size and move pool, the indices of cubes along a
+ * fixed sequence of moves, and the cubes and turned indices of a sample of indices. Changing
+ * the indexer, the move pool or the move tables of a stage changes the hash, so tables built
+ * before the change are rejected.
+ */
+pub(crate) fn definition_hash<'a, S: Stage<'a>>() -> u64 {
+    let mut values = vec![S::SIZE as u64];
+    values.extend(S::MOVE_POOL.iter().map(|turn| *turn as u64));
+
+    let mut cube = Cube::new();
+    for i in 0..64 {
+        cube.turn(&S::MOVE_POOL[i * 7 % S::MOVE_POOL.len()]);
+        values.push(S::indexer(&cube) as u64);
+    }
+    for index in (0..S::SIZE).step_by(S::SIZE / 64 + 1) {
+        values.push(S::indexer(&S::cube(index)) as u64);
+        values.extend((0..S::MOVE_POOL.len()).map(|turn| S::turn_index(index, turn) as u64));
+    }
+
+    let bytes: Vec<u8> = values.iter().flat_map(|value| value.to_le_bytes()).collect();
+    return fnv1a(FNV_OFFSET, &bytes)
+}
+
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &byte| (hash ^ byte as u64).wrapping_mul(FNV_PRIME))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::stages::{G1, G2};
+
+    #[test]
+    fn rejects_mismatches() {
+        let data = G1::ENCODING.encode(&vec![0; G1::SIZE]).unwrap();
+        let mut file = Header::new::<G1>(&data).to_bytes().to_vec();
+        file.extend_from_slice(&data);
+        assert_eq!(Header::check::<G1>(&file), Ok(&data[..]));
+
+        assert_eq!(Header::check::<G1>(&data), Err(FormatError::NotATable));
+        assert_eq!(
+            Header::check::<G2>(&file),
+            Err(FormatError::Stage { found: "G1".to_string(), expected: "G2".to_string() }),
+        );
+
+        let mut corrupt = file.clone();
+        *corrupt.last_mut().unwrap() ^= 1;
+        assert_eq!(Header::check::<G1>(&corrupt), Err(FormatError::Checksum));
+        assert!(Header::check_layout::<G1>(&corrupt).is_ok());
+
+        let mut truncated = file.clone();
+        truncated.pop();
+        assert!(matches!(Header::check::<G1>(&truncated), Err(FormatError::Size { .. })));
+
+        let mut old = file.clone();
+        old[4] = 0;
+        assert_eq!(Header::check::<G1>(&old), Err(FormatError::Version { found: 0, expected: FORMAT_VERSION }));
+
+        let mut redefined = file;
+        redefined[31] ^= 1;
+        assert_eq!(Header::check::<G1>(&redefined), Err(FormatError::Definition));
+    }
+}
@@ -1,106 +1,438 @@
+use super::encoding::Encoding;
+use super::header::{FormatError, Header};
+#[cfg(feature = "mmap")]
+use super::header::HEADER_LEN;
 use super::stages::Stage;
 use super::cube::Cube;
 use std::io::{Read, Write};
 use std::marker::PhantomData;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
+/// Reasons a table cannot be loaded or saved.
+#[derive(Debug)]
+pub enum TableError {
+    /// There is no file at the path.
+    NotFound(PathBuf),
+    /// The file is not a table of the stage, or its contents are damaged.
+    Corrupt(FormatError),
+    /**The file holds a table written by another version of the format or for an older
+     * definition of the stage, e.g. with another encoding. Rebuilding the table fixes this.
+     */
+    VersionMismatch(FormatError),
+    /// Reading or writing the file failed for another reason, e.g. missing permissions.
+    Io(std::io::Error),
+    /// A built table has a distance too large for the encoding of the stage.
+    TooDeep { distance: u8, encoding: Encoding },
+}
+
+impl std::fmt::Display for TableError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TableError::NotFound(path) => write!(f, "no table file at {}", path.display()),
+            TableError::Corrupt(error) => write!(f, "the table file is corrupt: {}", error),
+            TableError::VersionMismatch(error) => write!(f, "the table file is outdated: {}", error),
+            TableError::Io(error) => write!(f, "could not access the table file: {}", error),
+            TableError::TooDeep { distance, encoding } => {
+                write!(f, "the table has distance {}, which the {:?} encoding cannot store", distance, encoding)
+            },
+        }
+    }
+}
+
+impl std::error::Error for TableError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TableError::Corrupt(error) | TableError::VersionMismatch(error) => Some(error),
+            TableError::Io(error) => Some(error),
+            TableError::NotFound(_) | TableError::TooDeep { .. } => None,
+        }
+    }
+}
+
+impl From<FormatError> for TableError {
+    fn from(error: FormatError) -> Self {
+        match error {
+            // A stage changing its encoding is a change of its definition
+            FormatError::Version { .. } | FormatError::Encoding | FormatError::Definition => {
+                TableError::VersionMismatch(error)
+            },
+            _ => TableError::Corrupt(error),
+        }
+    }
+}
+
+/**When `LookupTable::load_or_build` builds a table instead of loading it, and whether the built
+ * table is saved.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildPolicy {
+    /// Never build; every load error is returned.
+    Never,
+    /// Build if there is no file at the path. Corrupt and outdated files are reported.
+    IfMissing,
+    /// Build if the file is missing, corrupt or outdated. Other I/O errors are reported.
+    IfInvalid,
+    /// Build whenever the table cannot be loaded.
+    Always,
+    /**Build whenever the table cannot be loaded, but never save it, so nothing is written. This
+     * is for read-only deployments, where the table is then built again on every start.
+     */
+    InMemory,
+}
+
+impl BuildPolicy {
+    /// Whether a table that failed to load with `error` is built.
+    pub(crate) fn allows(&self, error: &TableError) -> bool {
+        match (error, self) {
+            (_, BuildPolicy::Never) => false,
+            (TableError::NotFound(_), _) => true,
+            (_, BuildPolicy::IfMissing) => false,
+            (TableError::Corrupt(_) | TableError::VersionMismatch(_), _) => true,
+            (TableError::Io(_), policy) => matches!(policy, BuildPolicy::Always | BuildPolicy::InMemory),
+            (TableError::TooDeep { .. }, _) => false,
+        }
+    }
+
+    /// Whether a built table is saved.
+    pub(crate) fn saves(&self) -> bool {
+        *self != BuildPolicy::InMemory
+    }
+}
+
+/// Progress of building a table, reported after every depth of the search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuildProgress {
+    /// The largest distance found so far.
+    pub depth: u8,
+    /// Number of entries with a known distance.
+    pub filled: usize,
+    /// Total number of entries in the table.
+    pub size: usize,
+}
+
+/**The distance to solved of every index of the stage `S`, stored with the encoding chosen by
+ * `S::ENCODING`. Files hold the encoded table behind a `Header` identifying the stage, so
+ * tables of another stage, format or stage definition are rejected when loading.
+ */
 pub struct LookupTable<S> {
-    data: Box<[u8]>,
+    data: Storage,
     stage: PhantomData<S>
 }
 
+/// The encoded table, either owned or read straight from a memory mapped table file.
+enum Storage {
+    Owned(Box<[u8]>),
+    #[cfg(feature = "mmap")]
+    Mapped(memmap2::Mmap),
+}
+
+impl Deref for Storage {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Storage::Owned(data) => data,
+            // The header was checked when mapping the file
+            #[cfg(feature = "mmap")]
+            Storage::Mapped(map) => &map[HEADER_LEN..],
+        }
+    }
+}
+
 impl<'a, S: Stage<'a>> LookupTable<S> {
-    pub fn new(data_file: Option<&str>) -> Self {
-        match data_file {
-            None => {
-                return Self {data: Self::build_table(), stage: PhantomData}
-            },
-            Some(file_path) => {
-                match Self::load_data_from_file(file_path) {
-                    Ok(data) => return Self {data: data, stage: PhantomData},
-                    Err(_) => {
-                        let table = Self::new(None);
-                        table.save_data_to_file(file_path).unwrap();
-                        return table
-                    }
-                }
+    /// Loads the table saved at `path`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, TableError> {
+        let data = Self::load_data_from_file(path.as_ref())?;
+        Ok(Self {data: Storage::Owned(data), stage: PhantomData})
+    }
+
+    /// Reads a table from the contents of a table file, e.g. one embedded with `include_bytes!`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TableError> {
+        let data = Header::check::<S>(bytes)?;
+        Ok(Self {data: Storage::Owned(data.into()), stage: PhantomData})
+    }
+
+    /**Maps the table saved at `path` into memory instead of reading it. Entries are read from
+     * the file on demand and the pages are shared with every other process mapping the file,
+     * so this returns at once even for large tables. Only the header is checked; unlike
+     * `load`, the checksum is not verified, as that would read the whole table.
+     *
+     * The file must not be modified in place while mapped. `save` replaces files by renaming,
+     * which leaves existing mappings intact.
+     */
+    #[cfg(feature = "mmap")]
+    pub fn map(path: impl AsRef<Path>) -> Result<Self, TableError> {
+        let file = open(path.as_ref())?;
+        // SAFETY: table files are only ever replaced, never modified in place, see above
+        let map = unsafe { memmap2::Mmap::map(&file) }.map_err(TableError::Io)?;
+        Header::check_layout::<S>(&map)?;
+        Ok(Self {data: Storage::Mapped(map), stage: PhantomData})
+    }
+
+    /// Whether the table is read from a memory mapped file, see `map`.
+    pub fn is_mapped(&self) -> bool {
+        match self.data {
+            Storage::Owned(_) => false,
+            #[cfg(feature = "mmap")]
+            Storage::Mapped(_) => true,
+        }
+    }
+
+    /// Builds the table from scratch. Fails if the distances do not fit in `S::ENCODING`.
+    pub fn build() -> Result<Self, TableError> {
+        Self::build_with_progress(|_| {})
+    }
+
+    /**Loads the table saved at `path`, or builds it if `policy` allows for the error. A built
+     * table is saved at `path` unless the policy is `BuildPolicy::InMemory`. Failing to save is
+     * returned as `TableError::Io`, as the table would otherwise be built again on every start
+     * without notice; use `BuildPolicy::InMemory` where that is intended.
+     */
+    pub fn load_or_build(path: impl AsRef<Path>, policy: BuildPolicy) -> Result<Self, TableError> {
+        let error = match Self::load(&path) {
+            Ok(table) => return Ok(table),
+            Err(error) => error,
+        };
+        if !policy.allows(&error) {
+            return Err(error)
+        }
+        let table = Self::build()?;
+        if policy.saves() {
+            table.save(&path)?;
+        }
+        Ok(table)
+    }
+
+    /**Builds the table from scratch and saves it to `path`, replacing the file. This is the
+     * way to update a table that fails to load because the stage has changed.
+     */
+    pub fn rebuild(path: impl AsRef<Path>) -> Result<Self, TableError> {
+        let table = Self::build()?;
+        table.save(path)?;
+        Ok(table)
+    }
+
+    /**Builds the table from scratch, calling `progress` after every depth of the search. With
+     * the `parallel` feature, large tables are built on all threads of the rayon thread pool.
+     */
+    pub fn build_with_progress(progress: impl FnMut(&BuildProgress)) -> Result<Self, TableError> {
+        #[cfg(feature = "parallel")]
+        {
+            if S::SIZE >= PARALLEL_THRESHOLD {
+                return Self::from_data(&Self::build_table_parallel(progress))
             }
         }
+        Self::from_data(&Self::build_table(progress))
     }
 
+    /// A table holding `distances`, which must have been built for the stage `S`.
+    pub(crate) fn from_data(distances: &[u8]) -> Result<Self, TableError> {
+        assert_eq!(distances.len(), S::SIZE);
+        let data = S::ENCODING.encode(distances)
+            .map_err(|distance| TableError::TooDeep { distance, encoding: S::ENCODING })?;
+        Ok(Self {data: Storage::Owned(data), stage: PhantomData})
+    }
+
+    /**The distance to solved of `cube`, or `u8::MAX` if unknown. This is a single lookup, except
+     * for stages using `Encoding::Mod3`, see `eval_index`.
+     */
     pub fn eval(&self, cube: &Cube) -> u8 {
-        self.data[S::indexer(cube)]
+        self.eval_index(S::indexer(cube))
+    }
+
+    /**The distance to solved of the cubes with index `index`, or `u8::MAX` if unknown. With
+     * `Encoding::Mod3` this descends all the way to solved, trying the moves of the pool at
+     * every step, which costs up to a move pool of lookups per move of the distance. Searches
+     * stepping through neighbours should use `is_at_distance` instead.
+     */
+    pub fn eval_index(&self, index: usize) -> u8 {
+        let value = S::ENCODING.get(&self.data, index);
+        if S::ENCODING != Encoding::Mod3 || value == u8::MAX {
+            return value
+        }
+
+        // Only solved has no neighbour one move closer
+        let (mut index, mut value, mut distance) = (index, value, 0);
+        while let Some(next) = (0..S::MOVE_POOL.len())
+            .map(|turn| S::turn_index(index, turn))
+            .find(|&next| S::ENCODING.get(&self.data, next) == (value + 2) % 3)
+        {
+            index = next;
+            value = (value + 2) % 3;
+            distance += 1;
+        }
+        return distance
     }
 
-    fn load_data_from_file(file_path: &str) -> Result<Box<[u8]>, std::io::Error> {
+    /**Whether the entry `index` has distance `distance`, given that it is a neighbour of an
+     * entry with distance `distance + 1`. This takes a single lookup for every encoding.
+     */
+    pub(crate) fn is_at_distance(&self, index: usize, distance: u8) -> bool {
+        let value = S::ENCODING.get(&self.data, index);
+        match S::ENCODING {
+            Encoding::Mod3 => value == distance % 3,
+            _ => value == distance,
+        }
+    }
+
+    fn load_data_from_file(file_path: &Path) -> Result<Box<[u8]>, TableError> {
         // load from file
-        let mut file = std::fs::File::open(file_path)?;
+        let mut file = open(file_path)?;
         let mut buffer = Vec::new();
-        file.read_to_end(&mut buffer)?;
+        file.read_to_end(&mut buffer).map_err(TableError::Io)?;
 
-        if buffer.len() != S::SIZE {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                "Save file seems to be corrupt."
-            ))
-        } else {
-            return Ok(buffer.into())
-        }
+        return Ok(Header::check::<S>(&buffer)?.into())
     }
 
-    pub fn save_data_to_file(&self, file_path: &str) -> Result<(), std::io::Error> {
-        let mut file = std::fs::File::create(file_path)?;
-        file.write_all(&self.data)?;
-        Ok(())
+    /**Saves the table at `path`, replacing any existing file. The table is written to a
+     * temporary file next to `path` which is then renamed, so other processes never see a
+     * partially written table.
+     */
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), TableError> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let path = path.as_ref();
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(format!(".{}-{}.tmp", std::process::id(), COUNTER.fetch_add(1, Ordering::Relaxed)));
+        let temporary = PathBuf::from(temporary);
+
+        let write = || -> std::io::Result<()> {
+            let mut file = std::fs::File::create(&temporary)?;
+            file.write_all(&Header::new::<S>(&self.data).to_bytes())?;
+            file.write_all(&self.data)?;
+            file.sync_all()?;
+            std::fs::rename(&temporary, path)
+        };
+        write().map_err(|error| {
+            let _ = std::fs::remove_file(&temporary);
+            TableError::Io(error)
+        })
     }
 
-    /**Compute lookup table from scratch by Iterative Deepening Depth First Search (IDDFS). Although 
-     * slow, this is done to save on memory since the width in a Breadth First Search would be large.
+    /**Compute lookup table from scratch by a Breadth First Search over the table itself. Each
+     * sweep over the table finds the entries one move further from solved than the last. While
+     * the entries at the current depth (the frontier) are fewer than the entries not yet found,
+     * the frontier is expanded forwards. Afterwards it is cheaper to search backwards, checking
+     * for each entry not yet found whether a move leads to the frontier. Indices are turned by
+     * `S::turn_index`, so no cubes are needed besides the solved one.
      */
-    fn build_table() -> Box<[u8]> {
+    fn build_table(mut progress: impl FnMut(&BuildProgress)) -> Box<[u8]> {
         // `result` will hold the minimum distance (number of turns) from a solved cube
-        // to any configuration of edge orientations
         let mut result = vec![u8::MAX; S::SIZE];
-        let mut num_items = 0;
-
-        // Initialise the root
         result[S::indexer(&Cube::new())] = 0;
-        num_items += 1;
-
-        let mut depth_limit = 0;
-        while num_items < S::SIZE {
-            depth_limit += 1;
-            dbg!(&depth_limit, S::SIZE - num_items);
-
-            let mut queue = vec![(Cube::new(), 0)];
-            while let Some((parent, parent_depth)) = queue.pop() {
-                for turn in S::MOVE_POOL.iter() {
-                    let mut child = parent.clone();
-                    child.turn(turn);
-                    let index = S::indexer(&child);
-                    let depth = parent_depth + 1;
-
-                    if result[index] < depth {
-                        // child can be reached at a shallower depth, so don't add it to the queue
-                        continue;
-                    }
 
-                    if depth == depth_limit {
-                        if result[index] > depth {
-                            // This is the first time child is encountered, so record its depth
-                            num_items += 1;
-                            result[index] = depth;
+        let mut depth = 0;
+        let mut filled = 1;
+        let mut frontier = 1;
+        progress(&BuildProgress { depth, filled, size: S::SIZE });
+
+        while frontier > 0 {
+            let backwards = frontier > S::SIZE - filled;
+            frontier = 0;
+            for index in 0..S::SIZE {
+                if backwards {
+                    if result[index] != u8::MAX {
+                        continue
+                    }
+                    // The move pools are closed under inverses, so a move leads back to the
+                    // frontier exactly if some move from the frontier leads here
+                    let found = (0..S::MOVE_POOL.len())
+                        .any(|turn| result[S::turn_index(index, turn)] == depth);
+                    if found {
+                        result[index] = depth + 1;
+                        frontier += 1;
+                    }
+                } else {
+                    if result[index] != depth {
+                        continue
+                    }
+                    for turn in 0..S::MOVE_POOL.len() {
+                        let child = S::turn_index(index, turn);
+                        if result[child] == u8::MAX {
+                            result[child] = depth + 1;
+                            frontier += 1;
                         }
-                        // Child is at current depth limit, so don't add it to the queue
-                        continue;
                     }
-
-                    // Continue branch
-                    queue.push((child, depth));
                 }
             }
+
+            if frontier > 0 {
+                depth += 1;
+                filled += frontier;
+                progress(&BuildProgress { depth, filled, size: S::SIZE });
+            }
         }
         return result.into()
     }
+
+    /**The same search as `build_table`, with each sweep split into ranges of indices searched
+     * in parallel. Entries are set with atomic compare-and-swap, and as entries found in a
+     * sweep are given the next depth, which the sweep never looks for, the result does not
+     * depend on the order in which the ranges are searched. It is identical to `build_table`.
+     */
+    #[cfg(feature = "parallel")]
+    fn build_table_parallel(mut progress: impl FnMut(&BuildProgress)) -> Box<[u8]> {
+        use rayon::prelude::*;
+        use std::sync::atomic::AtomicU8;
+
+        let result: Vec<AtomicU8> = (0..S::SIZE).map(|_| AtomicU8::new(u8::MAX)).collect();
+        result[S::indexer(&Cube::new())].store(0, Ordering::Relaxed);
+        let get = |index: usize| result[index].load(Ordering::Relaxed);
+
+        let mut depth = 0;
+        let mut filled = 1;
+        let mut frontier = 1;
+        progress(&BuildProgress { depth, filled, size: S::SIZE });
+
+        while frontier > 0 {
+            let backwards = frontier > S::SIZE - filled;
+            frontier = (0..S::SIZE).into_par_iter().with_min_len(PARALLEL_RANGE).map(|index| {
+                if backwards {
+                    if get(index) != u8::MAX {
+                        return 0
+                    }
+                    let found = (0..S::MOVE_POOL.len())
+                        .any(|turn| get(S::turn_index(index, turn)) == depth);
+                    if found {
+                        result[index].store(depth + 1, Ordering::Relaxed);
+                        return 1
+                    }
+                    return 0
+                }
+                if get(index) != depth {
+                    return 0
+                }
+                // Only the thread whose swap succeeds counts the child
+                (0..S::MOVE_POOL.len())
+                    .filter(|&turn| result[S::turn_index(index, turn)]
+                        .compare_exchange(u8::MAX, depth + 1, Ordering::Relaxed, Ordering::Relaxed)
+                        .is_ok())
+                    .count()
+            }).sum();
+
+            if frontier > 0 {
+                depth += 1;
+                filled += frontier;
+                progress(&BuildProgress { depth, filled, size: S::SIZE });
+            }
+        }
+        return result.into_iter().map(AtomicU8::into_inner).collect()
+    }
+}
+
+/// Tables smaller than this are built serially, as splitting them up costs more than it gains.
+#[cfg(feature = "parallel")]
+const PARALLEL_THRESHOLD: usize = 1 << 16;
+/// Smallest range of indices searched by a single thread in a parallel build.
+#[cfg(feature = "parallel")]
+const PARALLEL_RANGE: usize = 1 << 12;
+
+fn open(path: &Path) -> Result<std::fs::File, TableError> {
+    std::fs::File::open(path).map_err(|error| match error.kind() {
+        std::io::ErrorKind::NotFound => TableError::NotFound(path.to_path_buf()),
+        _ => TableError::Io(error),
+    })
 }
 
 /* // Alternative implementations using a constant filepath for storing binary
@@ -138,4 +470,115 @@ impl<'a, S: Stage<'a>> LookupTable<S> {
         file.write_all(&self.data)?;
         Ok(())
     }
-} */
\ No newline at end of file
+} */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::solver::DATA_DIR;
+    use super::super::stages::{G1, G2, G3Pochmann, G4};
+
+    fn matches_shipped_table<'a, S: Stage<'a>>(file_name: &str) {
+        let mut depths = Vec::new();
+        let distances = LookupTable::<S>::build_table(|progress| depths.push(progress.depth));
+        let table = LookupTable::<S>::from_data(&distances).unwrap();
+        let shipped = LookupTable::<S>::load(format!("{}/{}", DATA_DIR, file_name)).unwrap();
+        assert!(table.data[..] == shipped.data[..], "{} differs from the shipped table", file_name);
+        assert_eq!(depths, (0..=*distances.iter().max().unwrap()).collect::<Vec<_>>());
+
+        // The exact distances are recovered whatever the encoding
+        for index in (0..S::SIZE).step_by(S::SIZE / 100 + 1) {
+            assert_eq!(table.eval_index(index), distances[index]);
+        }
+    }
+
+    #[test]
+    fn rebuilds_rejected_table() {
+        let path = std::env::temp_dir().join(format!("rubiks_cube_rebuild_g1_{}.dat", std::process::id()));
+        let mut file = std::fs::read(format!("{}/g1.dat", DATA_DIR)).unwrap();
+        *file.last_mut().unwrap() ^= 1;
+        std::fs::write(&path, &file).unwrap();
+
+        let error = LookupTable::<G1>::load(&path).err().unwrap();
+        assert!(matches!(error, TableError::Corrupt(FormatError::Checksum)));
+        assert_eq!(error.to_string(), "the table file is corrupt: the table does not match its checksum");
+        assert!(LookupTable::<G1>::load_or_build(&path, BuildPolicy::IfMissing).is_err());
+        assert!(matches!(TableError::from(FormatError::Encoding), TableError::VersionMismatch(_)));
+
+        let table = LookupTable::<G1>::rebuild(&path).unwrap();
+        assert!(LookupTable::<G1>::load(&path).unwrap().data[..] == table.data[..]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn load_or_build() {
+        let path = std::env::temp_dir().join(format!("rubiks_cube_missing_g1_{}.dat", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        assert!(matches!(LookupTable::<G1>::load(&path), Err(TableError::NotFound(_))));
+        assert!(matches!(LookupTable::<G1>::load_or_build(&path, BuildPolicy::Never), Err(TableError::NotFound(_))));
+
+        // A built table is saved for next time
+        let table = LookupTable::<G1>::load_or_build(&path, BuildPolicy::IfMissing).unwrap();
+        assert!(LookupTable::<G1>::load(&path).unwrap().data[..] == table.data[..]);
+        std::fs::remove_file(&path).unwrap();
+
+        // Failing to save is reported, unless the table is not to be saved
+        let unwritable = std::env::temp_dir()
+            .join(format!("rubiks_cube_missing_dir_{}", std::process::id()))
+            .join("g1.dat");
+        assert!(matches!(LookupTable::<G1>::load_or_build(&unwritable, BuildPolicy::IfInvalid), Err(TableError::Io(_))));
+        assert!(LookupTable::<G1>::load_or_build(&unwritable, BuildPolicy::InMemory).is_ok());
+        assert!(!unwritable.exists());
+    }
+
+    #[test]
+    #[cfg(feature = "mmap")]
+    fn maps_table() {
+        let path = format!("{}/g2.dat", DATA_DIR);
+        let mapped = LookupTable::<G2>::map(&path).unwrap();
+        let loaded = LookupTable::<G2>::load(&path).unwrap();
+        assert!(mapped.is_mapped() && !loaded.is_mapped());
+        assert!(mapped.data[..] == loaded.data[..]);
+        for index in (0..G2::SIZE).step_by(997) {
+            assert_eq!(mapped.eval_index(index), loaded.eval_index(index));
+        }
+
+        // The header is checked, but not the checksum
+        assert!(matches!(LookupTable::<G1>::map(&path), Err(TableError::Corrupt(FormatError::Stage { .. }))));
+        assert!(matches!(LookupTable::<G2>::map(format!("{}/missing.dat", DATA_DIR)), Err(TableError::NotFound(_))));
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn parallel_build_matches_serial() {
+        fn matches_serial<'a, S: Stage<'a>>() {
+            let mut serial = Vec::new();
+            let mut parallel = Vec::new();
+            let distances = LookupTable::<S>::build_table(|progress| serial.push(*progress));
+            // Several threads even on machines with a single core
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+            let built = pool.install(|| LookupTable::<S>::build_table_parallel(|progress| parallel.push(*progress)));
+            assert!(built == distances, "{} differs from the serial build", S::NAME);
+            assert_eq!(parallel, serial);
+        }
+        matches_serial::<G1>();
+        matches_serial::<G2>();
+        matches_serial::<G3Pochmann>();
+        matches_serial::<G4>();
+    }
+
+    #[test]
+    fn rejects_distances_too_large_for_encoding() {
+        let error = LookupTable::<G3Pochmann>::from_data(&vec![15; G3Pochmann::SIZE]).err().unwrap();
+        assert!(matches!(error, TableError::TooDeep { distance: 15, encoding: Encoding::Nibbles }));
+        assert!(LookupTable::<G4>::from_data(&vec![15; G4::SIZE]).is_ok());
+    }
+
+    #[test]
+    fn builds_shipped_tables() {
+        matches_shipped_table::<G1>("g1.dat");
+        matches_shipped_table::<G2>("g2.dat");
+        matches_shipped_table::<G3Pochmann>("g3.dat");
+        matches_shipped_table::<G4>("g4.dat");
+    }
+}
@@ -1,9 +1,17 @@
+use super::coordinates;
 use super::cube;
+use super::sequence;
 
+mod encoding;
+mod header;
 mod lookup_table;
 mod stages;
 mod solver;
+mod table_store;
 
-pub use lookup_table::LookupTable;
+pub use encoding::Encoding;
+pub use header::{FormatError, FORMAT_VERSION};
+pub use lookup_table::{BuildPolicy, BuildProgress, LookupTable, TableError};
 pub use stages::{G1, G2, G3Pochmann, G4, Stage};
-// pub use solver::solve;
\ No newline at end of file
+pub use solver::{solve, stage_solutions, SolveError, ThistlethwaiteSolver, DATA_DIR};
+pub use table_store::{TableStore, TABLE_DIR_VAR};
\ No newline at end of file
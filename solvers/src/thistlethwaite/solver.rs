@@ -1,5 +1,328 @@
-/* use super::cube::{Cube, Move};
+use super::cube::{Cube, CubeError, Move};
+use super::lookup_table::{BuildPolicy, LookupTable, TableError};
+use super::table_store::TableStore;
+use super::sequence::MoveSequence;
+use super::stages::{Stage, G1, G2, G3Pochmann, G4};
 
-pub fn solve(cube: &Cube) -> Vec<Move> {
-    vec![]
-} */
\ No newline at end of file
+/// Directory holding the pre-computed tables shipped with the crate.
+pub const DATA_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/thistlethwaite/data");
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveError {
+    /// The cube cannot be reached by turning a solved cube, so it cannot be solved either.
+    InvalidCube(CubeError),
+    /// No move in the pool of `stage` brings the cube closer to the next subgroup. This
+    /// only happens if the cube is in a state that is not reachable by legal moves.
+    StageFailed { stage: usize },
+}
+
+impl std::fmt::Display for SolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolveError::InvalidCube(error) => write!(f, "the cube is not solvable: {}", error),
+            SolveError::StageFailed { stage } => {
+                write!(f, "stage {} of the Thistlethwaite solver could not make progress", stage)
+            },
+        }
+    }
+}
+
+impl std::error::Error for SolveError {}
+
+impl From<CubeError> for SolveError {
+    fn from(error: CubeError) -> Self {
+        SolveError::InvalidCube(error)
+    }
+}
+
+/**Thistlethwaite solver owning the lookup tables of all four stages. Loading the tables
+ * is by far the most expensive part of solving, so a single solver should be kept around
+ * and reused for solving many cubes.
+ */
+pub struct ThistlethwaiteSolver {
+    g1: LookupTable<G1>,
+    g2: LookupTable<G2>,
+    g3: LookupTable<G3Pochmann>,
+    g4: LookupTable<G4>,
+    candidates: usize,
+}
+
+impl ThistlethwaiteSolver {
+    /**Loads the tables of all four stages from `store`. Tables that are missing, corrupt or
+     * outdated are built from scratch and saved in `store`.
+     */
+    pub fn new(store: &TableStore) -> Result<Self, TableError> {
+        Self::with_policy(store, BuildPolicy::IfInvalid)
+    }
+
+    /**Loads the tables from the user's table directory, see `TableStore::resolve`, copying the
+     * tables shipped with the crate there when they are missing or outdated, and building them
+     * only if those cannot be loaded either. Unlike `default`, this writes to the directory.
+     */
+    pub fn cached() -> Result<Self, TableError> {
+        Self::new(&TableStore::resolve(None)?.seeded_from(DATA_DIR))
+    }
+
+    /// Like `new`, but building tables as allowed by `policy`.
+    pub fn with_policy(store: &TableStore, policy: BuildPolicy) -> Result<Self, TableError> {
+        Ok(Self {
+            g1: store.load_or_build(policy)?,
+            g2: store.load_or_build(policy)?,
+            g3: store.load_or_build(policy)?,
+            g4: store.load_or_build(policy)?,
+            candidates: 1,
+        })
+    }
+
+    /**Considers up to `candidates` optimal solutions of each stage, and the same number of
+     * partial solutions after each stage, keeping those that are shortest once simplified.
+     * This finds solutions where the stages cancel more moves where they meet, at a cost
+     * that grows with `candidates`. The default of 1 takes the first solution of each stage.
+     * That partial solution is always kept, so more candidates never give a longer solution.
+     */
+    pub fn with_candidates(mut self, candidates: usize) -> Self {
+        self.candidates = candidates.max(1);
+        self
+    }
+
+    /**Finds a sequence of face turns solving `cube`. If the cube has been rotated as a whole,
+     * the turns refer to the faces in their current position. The solutions of the stages are
+     * simplified where they meet, see `MoveSequence::simplify`.
+     */
+    pub fn solve(&self, cube: &Cube) -> Result<Vec<Move>, SolveError> {
+        cube.validate()?;
+
+        // The stages only work with the centers in their home position
+        let home = cube.reoriented();
+        let solution = self.solve_reoriented(&home)?;
+        Ok(cube.turns_from_reoriented(&solution))
+    }
+
+    fn solve_reoriented(&self, cube: &Cube) -> Result<Vec<Move>, SolveError> {
+        let candidates = vec![(*cube, Vec::new())];
+        let candidates = self.next_stage(candidates, 1, |cube| stage_solutions(cube, &self.g1, self.candidates))?;
+        let candidates = self.next_stage(candidates, 2, |cube| stage_solutions(cube, &self.g2, self.candidates))?;
+        let candidates = self.next_stage(candidates, 3, |cube| stage_solutions(cube, &self.g3, self.candidates))?;
+        let candidates = self.next_stage(candidates, 4, |cube| stage_solutions(cube, &self.g4, self.candidates))?;
+        // The first minimum is taken, so ties are broken in favour of the first solutions
+        Ok(candidates.into_iter().min_by_key(|(_, moves)| moves.len()).unwrap().1)
+    }
+
+    /**Extends each partial solution by each of the `solutions` of the next stage, keeping the
+     * shortest partial solutions after simplifying. The first partial solution, which takes the
+     * first solution of every stage so far, is kept first whatever its length.
+     */
+    fn next_stage(
+        &self,
+        candidates: Vec<(Cube, Vec<Move>)>,
+        stage: usize,
+        solutions: impl Fn(&Cube) -> Option<Vec<Vec<Move>>>,
+    ) -> Result<Vec<(Cube, Vec<Move>)>, SolveError> {
+        let mut next = Vec::new();
+        for (cube, moves) in candidates {
+            for solution in solutions(&cube).ok_or(SolveError::StageFailed { stage })? {
+                let mut cube = cube;
+                cube.apply_moves(&solution);
+                let moves: MoveSequence = moves.iter().chain(&solution).copied().collect();
+                next.push((cube, moves.simplify().into_vec()));
+            }
+        }
+        let first = next.remove(0);
+        // The sort is stable, so ties are broken by the order of the move pools
+        next.sort_by_key(|(_, moves)| moves.len());
+        next.truncate(self.candidates - 1);
+        next.insert(0, first);
+        Ok(next)
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl ThistlethwaiteSolver {
+    /**Maps the tables of all four stages from `store` into memory, see `LookupTable::map`.
+     * Nothing is built, so the tables must have been saved in `store` before.
+     */
+    pub fn map(store: &TableStore) -> Result<Self, TableError> {
+        Ok(Self {
+            g1: store.map()?,
+            g2: store.map()?,
+            g3: store.map()?,
+            g4: store.map()?,
+            candidates: 1,
+        })
+    }
+}
+
+#[cfg(feature = "embedded-tables")]
+impl ThistlethwaiteSolver {
+    /**Solver using the tables shipped with the crate, embedded in the binary at compile time.
+     * No files are read, so the binary works wherever it is run from.
+     */
+    pub fn embedded() -> Self {
+        Self {
+            g1: LookupTable::from_bytes(include_bytes!("data/g1.dat")).expect("the embedded tables are valid"),
+            g2: LookupTable::from_bytes(include_bytes!("data/g2.dat")).expect("the embedded tables are valid"),
+            g3: LookupTable::from_bytes(include_bytes!("data/g3.dat")).expect("the embedded tables are valid"),
+            g4: LookupTable::from_bytes(include_bytes!("data/g4.dat")).expect("the embedded tables are valid"),
+            candidates: 1,
+        }
+    }
+}
+
+/**Uses the tables shipped with the crate. With the `embedded-tables` feature these are
+ * embedded in the binary. Otherwise they are read from `DATA_DIR`, and any that cannot be
+ * read, e.g. because the binary was moved, are built in memory without being saved. Nothing is
+ * ever written; see `cached` for a solver keeping its tables in the user's table directory.
+ */
+impl Default for ThistlethwaiteSolver {
+    #[cfg(feature = "embedded-tables")]
+    fn default() -> Self {
+        Self::embedded()
+    }
+
+    #[cfg(not(feature = "embedded-tables"))]
+    fn default() -> Self {
+        Self::with_policy(&TableStore::new(DATA_DIR), BuildPolicy::InMemory)
+            .expect("tables are built when they cannot be read")
+    }
+}
+
+/**Solve `cube` using the tables shipped with the crate. The tables are loaded on every
+ * call, so use a `ThistlethwaiteSolver` directly when solving more than a single cube, or a
+ * `batch::BatchSolver` to solve many cubes in parallel.
+ */
+pub fn solve(cube: &Cube) -> Result<Vec<Move>, SolveError> {
+    ThistlethwaiteSolver::default().solve(cube)
+}
+
+/**All optimal sequences of moves from `S::MOVE_POOL` bringing `cube` into the subgroup of
+ * the next stage, in the order of the move pool, but at most `limit` of them. Since the
+ * tables are exact, these are the sequences where every move lowers the distance by one.
+ * `None` if there are none, which only happens if the state is not reachable by
+ * `S::MOVE_POOL` or is missing from `table`.
+ */
+pub fn stage_solutions<'a, S: Stage<'a>>(cube: &Cube, table: &LookupTable<S>, limit: usize) -> Option<Vec<Vec<Move>>> {
+    let mut solutions = Vec::new();
+    let index = S::indexer(cube);
+    let steps = table.eval_index(index);
+    if steps == u8::MAX {
+        return None
+    }
+    descend(index, table, steps, &mut Vec::new(), &mut solutions, limit);
+    if solutions.is_empty() {
+        return None
+    }
+    return Some(solutions)
+}
+
+fn descend<'a, S: Stage<'a>>(
+    index: usize,
+    table: &LookupTable<S>,
+    steps: u8,
+    moves: &mut Vec<Move>,
+    solutions: &mut Vec<Vec<Move>>,
+    limit: usize,
+) {
+    if steps == 0 {
+        solutions.push(moves.clone());
+        return
+    }
+    for (i, turn) in S::MOVE_POOL.iter().enumerate() {
+        if solutions.len() >= limit {
+            return
+        }
+        let next = S::turn_index(index, i);
+        if table.is_at_distance(next, steps - 1) {
+            moves.push(*turn);
+            descend(next, table, steps - 1, moves, solutions, limit);
+            moves.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::cube::{Axis, ExtendedMove};
+    use super::super::cube::Move::*;
+    use crate::scramble::random_cube;
+    use crate::test_fixtures::{flipped_edge, scrambled, shipped_solver};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn solves_scramble() {
+        let mut cube = scrambled();
+
+        let solver = shipped_solver();
+        let solution = solver.solve(&cube).unwrap();
+        assert!(cube.apply_moves(&solution).is_solved());
+        assert!(solver.solve(&Cube::new()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn candidates() {
+        let mut cube = scrambled();
+
+        let solver = shipped_solver();
+        let first = solver.solve(&cube).unwrap();
+        let solver = solver.with_candidates(20);
+        let best = solver.solve(&cube).unwrap();
+        assert!(best.len() <= first.len());
+        assert!(cube.apply_moves(&best).is_solved());
+
+        // The partial solution of a single candidate is kept, so more never give longer solutions
+        let mut rng = StdRng::seed_from_u64(5);
+        let solvers = [1, 2, 3, 8].map(|candidates| shipped_solver().with_candidates(candidates));
+        for _ in 0..10 {
+            let cube = random_cube(&mut rng);
+            let lengths = solvers.each_ref().map(|solver| solver.solve(&cube).unwrap().len());
+            assert!(lengths.iter().all(|&length| length <= lengths[0]), "{:?}", lengths);
+        }
+    }
+
+    #[test]
+    fn enumerates_stage_solutions() {
+        let table = TableStore::new(DATA_DIR).load::<G1>().unwrap();
+        let mut cube = Cube::new();
+        cube.turn(&F);
+        assert_eq!(stage_solutions(&cube, &table, usize::MAX), Some(vec![vec![F], vec![Fp]]));
+        assert_eq!(stage_solutions(&cube, &table, 1), Some(vec![vec![F]]));
+        assert_eq!(stage_solutions(&Cube::new(), &table, 1), Some(vec![vec![]]));
+
+        // States missing from the table are reported instead of searched forever
+        let missing = LookupTable::<G1>::from_data(&vec![u8::MAX; G1::SIZE]).unwrap();
+        assert_eq!(stage_solutions(&Cube::new(), &missing, 1), None);
+    }
+
+    #[test]
+    #[cfg(feature = "embedded-tables")]
+    fn embedded_tables() {
+        let mut cube = Cube::new();
+        cube.apply_moves(&[R, U, Rp, Up, F2, D, Bp, L]);
+        let solution = ThistlethwaiteSolver::embedded().solve(&cube).unwrap();
+        assert!(cube.apply_moves(&solution).is_solved());
+    }
+
+    #[test]
+    #[cfg(feature = "mmap")]
+    fn mapped_tables() {
+        let mut cube = Cube::new();
+        cube.apply_moves(&[R, U, Rp, Up, F2, D, Bp, L]);
+        let solver = ThistlethwaiteSolver::map(&TableStore::new(DATA_DIR)).unwrap();
+        assert_eq!(solver.solve(&cube), shipped_solver().solve(&cube));
+    }
+
+    #[test]
+    fn solves_rotated_cube() {
+        let mut cube = Cube::new();
+        cube.turn_extended(&ExtendedMove::Rotation(Axis::X, 1)).turn_extended(&ExtendedMove::Rotation(Axis::Y, 1));
+        cube.apply_moves(&[R, U, Rp, Up, F2, D]);
+
+        let solution = shipped_solver().solve(&cube).unwrap();
+        assert!(cube.apply_moves(&solution).is_solved());
+    }
+
+    #[test]
+    fn rejects_invalid_cube() {
+        assert_eq!(solve(&flipped_edge()), Err(SolveError::InvalidCube(CubeError::FlippedEdge)));
+    }
+}
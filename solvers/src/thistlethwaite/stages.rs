@@ -1,15 +1,63 @@
-use super::cube::{Cube, Move, Edge, Corner, EDGES, CORNERS};
+use super::cube::{Cube, Move, Edge, Corner, EDGES, CORNERS, FACES};
+use super::encoding::Encoding;
+use super::coordinates::{
+    Coordinate, CombinedMoveTable, MoveTable, CornerOrientation, EdgeOrientation, ESliceCombination,
+    E_SLICE_EDGES, M_SLICE_EDGES, combination_rank, combination_unrank, digits, permutation_parity,
+    permutation_rank, permutation_unrank, place,
+};
+use std::marker::PhantomData;
+use std::sync::OnceLock;
 
 pub trait Stage<'a> {
-    const FILEPATH: &'a str;
+    /// Name identifying the table of the stage in table files, at most 16 bytes.
+    const NAME: &'a str;
+    /// Name of the file holding the table of the stage in a `TableStore`.
+    const FILE_NAME: &'a str;
     const MOVE_POOL: &'a [Move];
     const SIZE: usize;
+    /**How the table of the stage is stored. `Encoding::Mod3` makes `LookupTable::eval` descend
+     * to solved instead of a single lookup, so it only pays off for large tables.
+     */
+    const ENCODING: Encoding = Encoding::Bytes;
     fn indexer(cube: &Cube) -> usize;
+
+    /**A cube with index `index`, used to build the table by scanning over indices. Only the
+     * pieces the index depends on need to be placed correctly; the cube does not have to be
+     * reachable, as turning it must only give the right index.
+     */
+    fn cube(index: usize) -> Cube;
+
+    /**The index after applying the move `MOVE_POOL[turn]` to a cube with index `index`. By
+     * default the move is applied to `cube(index)`; stages built from coordinates override
+     * this with move tables, which is much faster.
+     */
+    fn turn_index(index: usize, turn: usize) -> usize {
+        let mut cube = Self::cube(index);
+        cube.turn(&Self::MOVE_POOL[turn]);
+        return Self::indexer(&cube)
+    }
+}
+
+/**The part `(index / STEP) % SIZE` of the index of stage `S` as a coordinate, for stages whose
+ * index combines independent parts as a mixed-radix number.
+ */
+struct StageCoordinate<S, const STEP: usize, const SIZE: usize>(PhantomData<S>);
+impl<S: Stage<'static>, const STEP: usize, const SIZE: usize> Coordinate for StageCoordinate<S, STEP, SIZE> {
+    const SIZE: usize = SIZE;
+
+    fn coordinate(cube: &Cube) -> usize {
+        S::indexer(cube) / STEP % SIZE
+    }
+
+    fn cube(coordinate: usize) -> Cube {
+        S::cube(coordinate * STEP)
+    }
 }
 
 pub struct G1;
 impl<'a> Stage<'a> for G1 {
-    const FILEPATH: &'a str = &"./data/g1.dat";
+    const NAME: &'a str = "G1";
+    const FILE_NAME: &'a str = "g1.dat";
     const SIZE: usize = 2048;
     const MOVE_POOL: &'a [Move] = &[
         Move::U, Move::Up, Move::U2,
@@ -25,18 +73,25 @@ impl<'a> Stage<'a> for G1 {
      * edges are needed to define a unique index (even parity constraint). 
      */
     fn indexer(cube: &Cube) -> usize {
-        // Edge orientation index
-        EDGES[..11]
-            .iter()
-            .enumerate()
-            .map(|(i, edge)| 2_usize.pow(i as u32) * cube.get_edge_orientation(edge) as usize)
-            .sum()
+        EdgeOrientation::coordinate(cube)
+    }
+
+    fn cube(index: usize) -> Cube {
+        EdgeOrientation::cube(index)
+    }
+
+    fn turn_index(index: usize, turn: usize) -> usize {
+        static TABLE: OnceLock<MoveTable> = OnceLock::new();
+        TABLE.get_or_init(|| MoveTable::new::<EdgeOrientation>(Self::MOVE_POOL)).turn(index, turn)
     }
 }
 
 pub struct G2;
 impl<'a> Stage<'a> for G2 {
-    const FILEPATH: &'a str = &"./data/g2.dat";
+    const NAME: &'a str = "G2";
+    const FILE_NAME: &'a str = "g2.dat";
+    // By far the largest table, so `eval` descending to solved is worth a quarter of the size
+    const ENCODING: Encoding = Encoding::Mod3;
     const SIZE: usize = 1082565;
     const MOVE_POOL: &'a [Move] = &[
         Move::U, Move::Up, Move::U2,
@@ -55,25 +110,43 @@ impl<'a> Stage<'a> for G2 {
      * (12 choose 4) = 495. All in all 3^7 * (12 choose 4) = 1082565 indices. 
      */
     fn indexer(cube: &Cube) -> usize {
-        // We treat the array of corner orientations as a base-3 number.
-        let corner_orientations = CORNERS[..7].iter()
-            .map(|corner| cube.get_corner_orientation(corner));
-        let corner_orientation_index = corner_orientations.enumerate()
-            .map(|(i, n)| 3_usize.pow(i as u32) * n as usize)
-            .sum::<usize>();
-
-        const E_SLICE_EDGES: [Edge; 4] = [Edge::RF, Edge::RB, Edge::LB, Edge::LF];
-        let e_slice_edge_positions = E_SLICE_EDGES.map(|edge| *cube.get_edge_position(&edge));
-        let e_slice_edges_index = combination_rank(&e_slice_edge_positions, &EDGES);
-
-        let index = corner_orientation_index * 495 + e_slice_edges_index;
-        return index
+        // Corner orientations as a base-3 number, and the co-lexicographic rank of the E-slice edge positions
+        return CornerOrientation::coordinate(cube) * 495 + ESliceCombination::coordinate(cube)
+    }
+
+    fn cube(index: usize) -> Cube {
+        let orientations = digits(index / 495, 3, 8);
+        let corners = CORNERS.map(|corner| (corner, orientations[corner as usize]));
+
+        let positions = combination_unrank::<4>(index % 495).map(|i| EDGES[i]);
+        let edges = place(&E_SLICE_EDGES, &positions, &EDGES);
+        return Cube::from_pieces(edges.map(|edge| (edge, 0)), corners, FACES)
+    }
+
+    fn turn_index(index: usize, turn: usize) -> usize {
+        static TABLE: OnceLock<CombinedMoveTable> = OnceLock::new();
+        TABLE.get_or_init(|| CombinedMoveTable::new(vec![
+            (495, MoveTable::new::<CornerOrientation>(Self::MOVE_POOL)),
+            (1, MoveTable::new::<ESliceCombination>(Self::MOVE_POOL)),
+        ])).turn(index, turn)
     }
 }
 
+// First 4 and last 4 each make up a tetrad. First 2 and last 2 in each tetrad is a pair.
+const PAIRED_CORNERS: [Corner; 8] = [
+    Corner::URF, Corner::ULB,
+    Corner::DRB, Corner::DLF,
+    Corner::URB, Corner::ULF,
+    Corner::DRF, Corner::DLB,
+];
+const REMAINING_EDGES: [Edge; 8] = [Edge::UF, Edge::DF, Edge::DB, Edge::UB, Edge::UR, Edge::UL, Edge::DL, Edge::DR];
+
 pub struct G3Pochmann;
 impl<'a> Stage<'a> for G3Pochmann {
-    const FILEPATH: &'a str = &"./data/g3.dat";
+    const NAME: &'a str = "G3Pochmann";
+    const FILE_NAME: &'a str = "g3.dat";
+    // Distances are at most 14
+    const ENCODING: Encoding = Encoding::Nibbles;
     const SIZE: usize = 352800;
     const MOVE_POOL: &'a [Move] = &[
         Move::U, Move::Up, Move::U2,
@@ -106,13 +179,6 @@ impl<'a> Stage<'a> for G3Pochmann {
      * All in all this gives 2520 * 70 * 2 = 352800 configurations
     */
     fn indexer(cube: &Cube) -> usize {
-        // First 4 and last 4 each make up a tetrad. First 2 and last 2 in each tetrad is a pair.
-        const PAIRED_CORNERS: [Corner; 8] = [
-            Corner::URF, Corner::ULB,
-            Corner::DRB, Corner::DLF,
-            Corner::URB, Corner::ULF,
-            Corner::DRF, Corner::DLB,
-        ];
         let corner_positions = PAIRED_CORNERS.map(|pos| *cube.get_corner_position(&pos));
         
         let mut positions = Vec::from(PAIRED_CORNERS);
@@ -133,18 +199,53 @@ impl<'a> Stage<'a> for G3Pochmann {
         
         let parity = permutation_parity(&corner_positions, &PAIRED_CORNERS);
 
-        const REMAINING_EDGES: [Edge; 8] = [Edge::UF, Edge::DF, Edge::DB, Edge::UB, Edge::UR, Edge::UL, Edge::DL, Edge::DR];
-        const M_SLICE_EDGES: [Edge; 4] = [Edge::UF, Edge::DF, Edge::DB, Edge::UB];
         let m_slice_edge_positions = M_SLICE_EDGES.map(|edge| *cube.get_edge_position(&edge));
         let m_slice_edge_index = combination_rank(&m_slice_edge_positions, &REMAINING_EDGES);
 
         return (corner_pairs_index * 70 + m_slice_edge_index) * 2 + parity as usize
     }
+
+    fn cube(index: usize) -> Cube {
+        let parity = index % 2 == 1;
+        let m_slice_edge_index = index / 2 % 70;
+        let corner_pairs_index = index / 140;
+        let pair_indices = [corner_pairs_index / 90, corner_pairs_index / 6 % 15, corner_pairs_index % 6];
+
+        // Place the pairs one at a time in the positions left over by the previous pairs
+        let mut positions = Vec::from(PAIRED_CORNERS);
+        let mut corner_positions = Vec::new();
+        for pair_index in pair_indices {
+            let pair = combination_unrank::<2>(pair_index).map(|i| positions[i]);
+            positions.retain(|pos| !pair.contains(pos));
+            corner_positions.extend(pair);
+        }
+        corner_positions.extend(positions);
+        if permutation_parity(&corner_positions, &PAIRED_CORNERS) != parity {
+            corner_positions.swap(0, 1);
+        }
+        let corners = place(&PAIRED_CORNERS, &corner_positions, &CORNERS);
+
+        let positions = combination_unrank::<4>(m_slice_edge_index).map(|i| REMAINING_EDGES[i]);
+        let edges = place(&M_SLICE_EDGES, &positions, &EDGES);
+        return Cube::from_pieces(edges.map(|edge| (edge, 0)), corners.map(|corner| (corner, 0)), FACES)
+    }
+
+    fn turn_index(index: usize, turn: usize) -> usize {
+        static TABLE: OnceLock<CombinedMoveTable> = OnceLock::new();
+        TABLE.get_or_init(|| CombinedMoveTable::new(vec![
+            (140, MoveTable::new::<StageCoordinate<G3Pochmann, 140, 2520>>(Self::MOVE_POOL)),
+            (2, MoveTable::new::<StageCoordinate<G3Pochmann, 2, 70>>(Self::MOVE_POOL)),
+            (1, MoveTable::new::<StageCoordinate<G3Pochmann, 1, 2>>(Self::MOVE_POOL)),
+        ])).turn(index, turn)
+    }
 }
 
 pub struct G4;
 impl<'a> Stage<'a> for G4 {
-    const FILEPATH: &'a str = &"./data/g4.dat";
+    const NAME: &'a str = "G4";
+    const FILE_NAME: &'a str = "g4.dat";
+    // Distances reach 15, which nibbles reserve for unknown entries
+    const ENCODING: Encoding = Encoding::Bytes;
     const SIZE: usize = 663552;
     const MOVE_POOL: &'a [Move] = &[
         Move::U2,
@@ -178,7 +279,7 @@ impl<'a> Stage<'a> for G4 {
 
         let slice = [Edge::UR, Edge::UL, Edge::DL, Edge::DR];
         let partial_permutation: Vec<usize> = [Edge::UR, Edge::UL].iter()
-            .map(|edge| *cube.get_edge_position(&edge))
+            .map(|edge| *cube.get_edge_position(edge))
             .map(|edge| slice.iter().position(|&x| x == edge).unwrap())
             .collect();
         let s_slice_index = combination_rank(&partial_permutation, &[0, 1, 2, 3]) * 2 + (partial_permutation[0] < partial_permutation[1]) as usize;
@@ -187,37 +288,47 @@ impl<'a> Stage<'a> for G4 {
         
         return edge_index * 96 + corner_index
     }
-}
 
+    fn cube(index: usize) -> Cube {
+        let corner_index = index % 96;
+        let edge_index = index / 96;
 
-/**Computes a unique index for `combination` between all similar sized combinations of 
- * `ordering`. (a combination is an unordered subset). In particular, the computed index
- * is the co-lexicographic rank of the combination, where the lexicographic ordering is
- * defined by `ordering`.
- * 
- * Implementation from: https://computationalcombinatorics.wordpress.com/2012/09/10/ranking-and-unranking-of-combinations-and-permutations/
- * First the combination of items is transformed to an integer combination. Then the rank
- * of this combination is computed by the formula: rank(S) = Σ_{i=0}^{k-1} (S_i choose i+1),
- * where S is the list of integers in increasing order. Justification of the formula is not 
- * very well described at the source. Will have to investigate this at some point.
- */
-fn combination_rank<T: PartialEq>(combination: &[T], ordering: &[T]) -> usize {
-    let mut combination: Vec<usize> = combination.iter()
-        .map(|x| ordering.iter().position(|y| x == y).unwrap())
-        .collect();
-    combination.sort();
-    combination.iter()
-        .enumerate()
-        .map(|(i, &elem)| binom(elem, i + 1))
-        .sum()
-}
+        let tetrad = [Corner::URF, Corner::ULB, Corner::DRB, Corner::DLF];
+        let mut pieces: Vec<Corner> = Vec::from(tetrad);
+        let mut positions: Vec<Corner> = permutation_unrank(corner_index / 4, 4).iter().map(|&i| tetrad[i]).collect();
+        let tetrad = [Corner::URB, Corner::ULF, Corner::DRF, Corner::DLB];
+        pieces.push(Corner::URB);
+        positions.push(tetrad[corner_index % 4]);
+        let corners = place(&pieces, &positions, &CORNERS);
+
+        let e_slice = [Edge::RF, Edge::RB, Edge::LB, Edge::LF];
+        let mut pieces: Vec<Edge> = Vec::from(e_slice);
+        let mut positions: Vec<Edge> = permutation_unrank(edge_index % 24, 4).iter().map(|&i| e_slice[i]).collect();
+
+        let m_slice = [Edge::UF, Edge::DF, Edge::DB, Edge::UB];
+        pieces.extend(m_slice);
+        positions.extend(permutation_unrank(edge_index / 24 % 24, 4).iter().map(|&i| m_slice[i]));
 
-/// Computes the binomial coefficient (n choose k).
-fn binom(n: usize, k: usize) -> usize {
-    if k > n {
-        0
-    } else {
-        (0..k).fold(1, |res, i| (res * (n - i)) / (i + 1))
+        let s_slice_index = edge_index / 576;
+        let [first, second] = combination_unrank::<2>(s_slice_index / 2);
+        let (ur, ul) = if s_slice_index % 2 == 1 { (first, second) } else { (second, first) };
+        let s_slice = [Edge::UR, Edge::UL, Edge::DL, Edge::DR];
+        pieces.extend([Edge::UR, Edge::UL]);
+        positions.extend([s_slice[ur], s_slice[ul]]);
+        let edges = place(&pieces, &positions, &EDGES);
+
+        return Cube::from_pieces(edges.map(|edge| (edge, 0)), corners.map(|corner| (corner, 0)), FACES)
+    }
+
+    fn turn_index(index: usize, turn: usize) -> usize {
+        static TABLE: OnceLock<CombinedMoveTable> = OnceLock::new();
+        TABLE.get_or_init(|| CombinedMoveTable::new(vec![
+            (96 * 576, MoveTable::new::<StageCoordinate<G4, { 96 * 576 }, 12>>(Self::MOVE_POOL)),
+            (96 * 24, MoveTable::new::<StageCoordinate<G4, { 96 * 24 }, 24>>(Self::MOVE_POOL)),
+            (96, MoveTable::new::<StageCoordinate<G4, 96, 24>>(Self::MOVE_POOL)),
+            (4, MoveTable::new::<StageCoordinate<G4, 4, 24>>(Self::MOVE_POOL)),
+            (1, MoveTable::new::<StageCoordinate<G4, 1, 4>>(Self::MOVE_POOL)),
+        ])).turn(index, turn)
     }
 }
 
@@ -225,6 +336,7 @@ fn binom(n: usize, k: usize) -> usize {
  * from Wendy Myrvold, Frank Ruskey, Ranking and unranking permutations in linear time, 
  * Information Processing Letters, Volume 79, Issue 6, 2001, Pages 281-284,
  */
+#[allow(dead_code)]
 fn permutation_rank_recursive<T: PartialEq>(permutation: &[T], initial: &[T]) -> usize {
     let pi: Vec<usize> = permutation.iter()
         .map(|x| initial.iter().position(|y| x == y).unwrap())
@@ -247,47 +359,40 @@ fn _permutation_rank_recursive(n: usize, mut pi: Vec<usize>, mut pi_inv: Vec<usi
     return s + n * _permutation_rank_recursive(n - 1, pi, pi_inv)
 }
 
-/// Lexicographic rank of a permutation
-fn permutation_rank<T: PartialEq>(permutation: &[T], initial: &[T]) -> usize {
-    let pi: Vec<usize> = permutation.iter()
-        .map(|x| initial.iter().position(|y| x == y).unwrap())
-        .collect();
-    
-    let n = pi.len();
-    let mut digits: Vec<usize> = (0..n).collect();
-    let mut factorial: usize = (2..n).product();
-    let mut index = 0;
-    for i in 0..n-1 {
-        let q = digits.iter()
-            .position(|x| x == &pi[i])
-            .unwrap();
-        index += factorial * q;
-        digits.remove(q);
-        factorial /= n - 1 - i;
-    }
-    return index;
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-fn permutation_parity<T: PartialEq>(permutation: &[T], initial: &[T]) -> bool {
-    let permutation: Vec<usize> = permutation.iter()
-        .map(|x| initial.iter().position(|y| x == y).unwrap())
-        .collect();
+    #[test]
+    fn indices_in_range() {
+        let mut cube = Cube::new();
+        assert_eq!(G1::indexer(&cube), 0);
+        for turn in G1::MOVE_POOL.iter().cycle().step_by(5).take(50) {
+            cube.turn(turn);
+            assert!(G1::indexer(&cube) < G1::SIZE);
+            assert!(G2::indexer(&cube) < G2::SIZE);
+        }
 
-    let mut parity = false;
-    for i in 0..permutation.len() {
-        for j in i+1..permutation.len() {
-            parity ^= permutation[i] > permutation[j];
+        // The remaining indexers are only defined on their respective subgroups
+        let mut cube = Cube::new();
+        for turn in G4::MOVE_POOL.iter().cycle().step_by(5).take(50) {
+            cube.turn(turn);
+            assert!(G3Pochmann::indexer(&cube) < G3Pochmann::SIZE);
+            assert!(G4::indexer(&cube) < G4::SIZE);
         }
     }
-    return parity
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    fn roundtrip<S: Stage<'static>>() {
+        for index in (0..S::SIZE).step_by(S::SIZE / 1000 + 1) {
+            assert_eq!(S::indexer(&S::cube(index)), index);
+        }
+    }
 
     #[test]
-    fn test1() {
-        
+    fn cubes_have_their_index() {
+        roundtrip::<G1>();
+        roundtrip::<G2>();
+        roundtrip::<G3Pochmann>();
+        roundtrip::<G4>();
     }
 }
\ No newline at end of file
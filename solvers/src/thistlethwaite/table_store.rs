@@ -0,0 +1,177 @@
+use super::lookup_table::{BuildPolicy, LookupTable, TableError};
+use super::stages::Stage;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// Environment variable overriding the directory of the default `TableStore`.
+pub const TABLE_DIR_VAR: &str = "RUBIKS_CUBE_TABLES";
+
+/**A directory holding the table files of stages, each named by `Stage::FILE_NAME`. The
+ * directory is created when the first table is saved, and tables are saved atomically, so
+ * several processes can share a store.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableStore {
+    dir: PathBuf,
+    seed: Option<PathBuf>,
+}
+
+impl TableStore {
+    /// The store in `dir`.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into(), seed: None }
+    }
+
+    /**Copies tables that need building from `dir` instead, if they can be loaded from there,
+     * e.g. from the tables shipped with the crate in `DATA_DIR`.
+     */
+    pub fn seeded_from(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.seed = Some(dir.into());
+        self
+    }
+
+    /**The store in `dir` if given, and otherwise in the directory named by the environment
+     * variable `RUBIKS_CUBE_TABLES`, or else `rubiks_cube` in the user's cache directory
+     * (`$XDG_CACHE_HOME`, defaulting to `~/.cache`, or `%LOCALAPPDATA%` on Windows).
+     */
+    pub fn resolve(dir: Option<&Path>) -> Result<Self, TableError> {
+        Self::resolve_with(dir, std::env::var_os(TABLE_DIR_VAR), cache_dir())
+    }
+
+    fn resolve_with(dir: Option<&Path>, env_dir: Option<OsString>, cache_dir: Option<PathBuf>) -> Result<Self, TableError> {
+        if let Some(dir) = dir {
+            return Ok(Self::new(dir))
+        }
+        if let Some(dir) = env_dir.filter(|dir| !dir.is_empty()) {
+            return Ok(Self::new(dir))
+        }
+        match cache_dir {
+            Some(dir) => Ok(Self::new(dir.join("rubiks_cube"))),
+            None => Err(TableError::Io(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("no table directory given, {} is not set and there is no cache directory", TABLE_DIR_VAR),
+            ))),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The path of the table file of the stage `S`.
+    pub fn path<'a, S: Stage<'a>>(&self) -> PathBuf {
+        self.dir.join(S::FILE_NAME)
+    }
+
+    pub fn load<'a, S: Stage<'a>>(&self) -> Result<LookupTable<S>, TableError> {
+        LookupTable::load(self.path::<S>())
+    }
+
+    /// Maps the table of the stage `S` into memory, see `LookupTable::map`.
+    #[cfg(feature = "mmap")]
+    pub fn map<'a, S: Stage<'a>>(&self) -> Result<LookupTable<S>, TableError> {
+        LookupTable::map(self.path::<S>())
+    }
+
+    /// Saves `table` in the store, creating the directory if needed.
+    pub fn save<'a, S: Stage<'a>>(&self, table: &LookupTable<S>) -> Result<(), TableError> {
+        std::fs::create_dir_all(&self.dir).map_err(TableError::Io)?;
+        table.save(self.path::<S>())
+    }
+
+    /**Loads the table of the stage `S`, or builds it if `policy` allows for the error. If the
+     * store is seeded, the table is copied from the seed rather than built whenever possible.
+     * Like `LookupTable::load_or_build`, the table is then saved in the store unless the policy
+     * is `BuildPolicy::InMemory`, and failing to save is returned as an error.
+     */
+    pub fn load_or_build<'a, S: Stage<'a>>(&self, policy: BuildPolicy) -> Result<LookupTable<S>, TableError> {
+        let error = match self.load::<S>() {
+            Ok(table) => return Ok(table),
+            Err(error) => error,
+        };
+        if !policy.allows(&error) {
+            return Err(error)
+        }
+        let seeded = self.seed.as_ref().and_then(|seed| LookupTable::load(seed.join(S::FILE_NAME)).ok());
+        let table = match seeded {
+            Some(table) => table,
+            None => LookupTable::build()?,
+        };
+        if policy.saves() {
+            self.save(&table)?;
+        }
+        Ok(table)
+    }
+}
+
+fn cache_dir() -> Option<PathBuf> {
+    let non_empty = |name| std::env::var_os(name).filter(|dir| !dir.is_empty());
+    if let Some(dir) = non_empty("XDG_CACHE_HOME") {
+        return Some(dir.into())
+    }
+    if cfg!(windows) {
+        return non_empty("LOCALAPPDATA").map(PathBuf::from)
+    }
+    return non_empty("HOME").map(|home| PathBuf::from(home).join(".cache"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::cube::Cube;
+    use super::super::solver::DATA_DIR;
+    use super::super::stages::{G1, G2};
+
+    #[test]
+    fn resolves_directory() {
+        let explicit = Path::new("/explicit");
+        let env_dir = Some(OsString::from("/from_env"));
+        let cache = Some(PathBuf::from("/cache"));
+
+        let resolve = |dir, env_dir, cache| TableStore::resolve_with(dir, env_dir, cache).unwrap();
+        assert_eq!(resolve(Some(explicit), env_dir.clone(), cache.clone()).dir(), explicit);
+        assert_eq!(resolve(None, env_dir, cache.clone()).dir(), Path::new("/from_env"));
+        assert_eq!(resolve(None, Some(OsString::new()), cache.clone()).dir(), Path::new("/cache/rubiks_cube"));
+        assert!(TableStore::resolve_with(None, None, None).is_err());
+        assert_eq!(TableStore::new("/tables").path::<G1>(), Path::new("/tables/g1.dat"));
+    }
+
+    #[test]
+    fn seeds_tables() {
+        let dir = std::env::temp_dir().join("rubiks_cube_seeded").join(std::process::id().to_string());
+        let _ = std::fs::remove_dir_all(&dir);
+        let store = TableStore::new(&dir).seeded_from(DATA_DIR);
+        assert!(matches!(store.load_or_build::<G2>(BuildPolicy::Never), Err(TableError::NotFound(_))));
+
+        // The shipped table is copied rather than built
+        store.load_or_build::<G2>(BuildPolicy::IfMissing).unwrap();
+        let shipped = TableStore::new(DATA_DIR).path::<G2>();
+        assert_eq!(std::fs::read(store.path::<G2>()).unwrap(), std::fs::read(shipped).unwrap());
+
+        // Tables missing from the seed are built
+        let empty = TableStore::new(&dir).seeded_from(dir.join("missing"));
+        assert_eq!(empty.load_or_build::<G1>(BuildPolicy::IfMissing).unwrap().eval(&Cube::new()), 0);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn creates_directory() {
+        let dir = std::env::temp_dir().join("rubiks_cube_store").join(std::process::id().to_string());
+        let _ = std::fs::remove_dir_all(&dir);
+        let store = TableStore::new(&dir);
+        assert!(matches!(store.load::<G1>(), Err(TableError::NotFound(_))));
+
+        let table = store.load_or_build::<G1>(BuildPolicy::IfMissing).unwrap();
+        assert!(store.path::<G1>().exists());
+        assert_eq!(store.load::<G1>().unwrap().eval_index(1), table.eval_index(1));
+
+        // Only the table is left in the directory
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        // A directory that cannot be created is reported, unless nothing is to be saved
+        let blocked = TableStore::new(store.path::<G1>().join("tables"));
+        assert!(matches!(blocked.load_or_build::<G1>(BuildPolicy::IfMissing), Err(TableError::Io(_))));
+        assert!(blocked.load_or_build::<G1>(BuildPolicy::InMemory).is_ok());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}